@@ -17,10 +17,22 @@ const INPUT_FILE: &str = "input-file";
 const OUTPUT_FOLDER: &str = "output-folder";
 const GENERATED: &str = "generated";
 const API_VERSION: &str = "api-version";
+const STRICT: &str = "strict";
+const DRY_RUN: &str = "dry-run";
+const WATCH: &str = "watch";
 
-pub fn config_try_new() -> Result<Config> {
+/// The config of the run and how to run it
+pub struct Args {
+    pub config: Config,
+    /// Regenerate when the spec files change
+    pub watch: bool,
+}
+
+pub fn args_try_new() -> Result<Args> {
     let arg_matches = new_app().get_matches();
-    config_try_new_from_matches(&arg_matches)
+    let config = config_try_new_from_matches(&arg_matches)?;
+    let watch = arg_matches.is_present(WATCH);
+    Ok(Args { config, watch })
 }
 
 fn config_try_new_from_matches(arg_matches: &ArgMatches) -> Result<Config> {
@@ -37,11 +49,16 @@ fn config_try_new_from_matches(arg_matches: &ArgMatches) -> Result<Config> {
         .into();
     let api_version = arg_matches.value_of(API_VERSION).map(String::from);
     let box_properties = HashSet::new();
+    let strict = arg_matches.is_present(STRICT);
+    let dry_run = arg_matches.is_present(DRY_RUN);
     Ok(Config {
         input_files,
         output_folder,
         api_version,
         box_properties,
+        strict,
+        dry_run,
+        ..Config::default()
     })
 }
 
@@ -69,6 +86,17 @@ fn new_app() -> App<'static> {
                 .long(API_VERSION)
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(STRICT)
+                .about("fail instead of warning when a $ref does not resolve")
+                .long(STRICT),
+        )
+        .arg(
+            Arg::new(DRY_RUN)
+                .about("print the paths of the generated files without writing them")
+                .long(DRY_RUN),
+        )
+        .arg(Arg::new(WATCH).about("regenerate when the spec files change").long(WATCH))
 }
 
 #[cfg(test)]
@@ -107,6 +135,33 @@ mod tests {
         assert_eq!(m.value_of(OUTPUT_FOLDER).unwrap(), "src");
     }
 
+    #[test]
+    fn api_version() -> Result<()> {
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json", "--api-version", "2020-06-01"])?;
+        assert_eq!(config_try_new_from_matches(&m)?.api_version.as_deref(), Some("2020-06-01"));
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json"])?;
+        assert_eq!(config_try_new_from_matches(&m)?.api_version, None);
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run() -> Result<()> {
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json", "--dry-run"])?;
+        assert!(config_try_new_from_matches(&m)?.dry_run);
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json"])?;
+        assert!(!config_try_new_from_matches(&m)?.dry_run);
+        Ok(())
+    }
+
+    #[test]
+    fn watch() -> Result<()> {
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json", "--watch"])?;
+        assert!(m.is_present(WATCH));
+        let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json"])?;
+        assert!(!m.is_present(WATCH));
+        Ok(())
+    }
+
     #[test]
     fn test_new_config() -> Result<()> {
         let m = new_app().try_get_matches_from(vec![NAME, "--input-file=abc.json", "--input-file=def.json", "--output-folder=src"]);
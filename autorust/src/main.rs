@@ -1,13 +1,32 @@
 mod cli;
+mod watch;
 
-use autorust_codegen::run;
-use cli::config_try_new;
+use autorust_codegen::{run, Config, RunResult};
+use cli::args_try_new;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
 
 fn main() -> Result<()> {
-    let config = config_try_new()?;
-    run(config)?;
+    let args = args_try_new()?;
+    generate(args.config.clone())?;
+    if args.watch {
+        watch::watch(&args.config, generate)?;
+    }
+    Ok(())
+}
+
+fn generate(config: Config) -> Result<()> {
+    if let RunResult::DryRun(dry_run) = run(config)? {
+        for (file, _) in &dry_run.files {
+            println!("{}", file.display());
+        }
+        println!(
+            "{} files for {} models and {} operations",
+            dry_run.files.len(),
+            dry_run.models,
+            dry_run.operations
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,50 @@
+//! Regenerates when the spec files change.
+
+use crate::Result;
+use autorust_codegen::{Config, Spec};
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::channel,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for the writes to a file to settle
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watch the folders of the input files and the files they reference, running `generate` after each change.
+pub fn watch(config: &Config, generate: fn(Config) -> Result<()>) -> Result<()> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::watcher(tx, DEBOUNCE)?;
+    for folder in get_spec_folders(config)? {
+        watcher.watch(&folder, RecursiveMode::NonRecursive)?;
+        println!("watching {}", folder.display());
+    }
+    loop {
+        match rx.recv()? {
+            DebouncedEvent::Write(file) | DebouncedEvent::Create(file) => {
+                let start = Instant::now();
+                let result = generate(config.clone());
+                let now = chrono::Local::now().format("%H:%M:%S");
+                match result {
+                    Ok(()) => println!("{} regenerated for {} in {:?}", now, file.display(), start.elapsed()),
+                    Err(error) => eprintln!("{} ERROR {}", now, error),
+                }
+            }
+            DebouncedEvent::Error(error, _) => return Err(error.into()),
+            _ => {}
+        }
+    }
+}
+
+/// The folders of the input files and all the files they reference
+fn get_spec_folders(config: &Config) -> Result<HashSet<PathBuf>> {
+    let spec = Spec::read_files(&config.input_files)?;
+    Ok(spec
+        .docs()
+        .keys()
+        .filter_map(|file| file.parent())
+        .map(|folder| if folder == Path::new("") { Path::new(".") } else { folder }.to_path_buf())
+        .collect())
+}
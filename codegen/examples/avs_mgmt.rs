@@ -13,6 +13,7 @@ fn main() -> Result<()> {
         output_folder: output_folder.into(),
         input_files: input_files.iter().map(Into::into).collect(),
         box_properties: HashSet::new(),
+        ..Config::default()
     })?;
 
     Ok(())
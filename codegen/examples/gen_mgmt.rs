@@ -1,90 +1,31 @@
-// cargo run --example gen_mgmt --release
+// cargo run --example gen_mgmt --release -- --jobs 4
 // https://github.com/Azure/azure-rest-api-specs/blob/master/specification/compute/resource-manager
 use autorust_codegen::{
     self, cargo_toml,
     config_parser::{to_api_version, to_mod_name},
-    get_mgmt_configs, lib_rs, path, Config, PropertyName, SpecConfigs,
+    gen_config::{self, GenConfig},
+    get_mgmt_configs, lib_rs, path, Config, RunResult, SpecConfigs,
 };
 use heck::SnakeCase;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
 
-use std::{collections::HashSet, fs, path::PathBuf};
+use std::{fs, path::Path, sync::Mutex};
 
 const OUTPUT_FOLDER: &str = "../azure-sdk-for-rust/services/mgmt";
 
-const ONLY_SERVICES: &[&str] = &[
-    // "vmware",
-    // "network",
-];
+/// Also create a workspace of the generated crates, for an output folder that is not in another workspace
+const CREATE_WORKSPACE: bool = false;
 
-const SKIP_SERVICES: &[&str] = &[
-    "automation",                 // TODO #81 DataType::File
-    "deploymentmanager",          // TODO #80 path parameters
-    "deviceprovisioningservices", // TODO #82 certificate_name used as parameter more than once
-    "dnc",                        // https://github.com/Azure/azure-rest-api-specs/pull/11578 two ControllerDetails types
-    "mixedreality",               // TODO #83 AccountKeyRegenerateRequest not generated
-    "netapp",                     // Ident "10minutely"
-    "powerplatform",              // https://github.com/Azure/azure-rest-api-specs/pull/11580 incorrect ref & duplicate Operations_List
-    "service-map",                // Ident "Ref:machine"
-    "servicefabric",              // https://github.com/Azure/azure-rest-api-specs/pull/11581 allOf mistakes and duplicate Operations_List
-    "servicefabricmanagedclusters",
-    "web", // TODO #81 DataType::File
-];
+/// The config file in the working directory, which replaces the built-in one
+const GEN_CONFIG_FILE: &str = "gen_mgmt.toml";
 
-const SKIP_SERVICE_TAGS: &[(&str, &str)] = &[
-    ("analysisservices", "package-2017-08"),
-    ("authorization", "package-2018-05-01-preview"),
-    ("authorization", "package-2021-03-01-preview-only"),
-    ("azureactivedirectory", "package-preview-2020-07"),
-    ("compute", "package-2020-10-01-preview"),      // TODO #81 DataType::File
-    ("compute", "package-2020-10-01-preview-only"), // TODO #81 DataType::File
-    ("compute", "package-2021-03-01"),              // TODO #81 DataType::File
-    ("compute", "package-2021-03-01-only"),         // TODO #81 DataType::File
-    ("consumption", "package-2019-11"),             // ReservationRecommendationDetails_Get has a path and query param both named "scope"
-    // datamigration, same error for all
-    // SchemaNotFound MigrateSqlServerSqlDbTask.json ValidationStatus, but may be buried
-    ("datamigration", "package-2018-07-15-preview"),
-    ("datamigration", "package-2018-04-19"),
-    ("datamigration", "package-2018-03-31-preview"),
-    ("datamigration", "package-2018-03-15-preview"),
-    ("datamigration", "package-2017-11-15-preview"),
-    ("mediaservices", "package-2019-05-preview"), // invalid unicode character of a dash instead of a hyphen https://github.com/Azure/azure-rest-api-specs/pull/11576
-    ("marketplace", "package-composite-v1"),
-    ("network", "package-2017-03-30-only"), // SchemaNotFound 2017-09-01/network.json SubResource
-    ("recoveryservicesbackup", "package-2020-07"), // duplicate fn get_operation_status
-    ("recoveryservicesbackup", "package-2020-10"), // duplicate fn get_operation_status
-    ("recoveryservicessiterecovery", "package-2016-08"), // duplicate package-2016-08 https://github.com/Azure/azure-rest-api-specs/pull/11287
-    ("resources", "package-policy-2020-03"),
-    ("resources", "package-policy-2020-09"), // SchemaNotFound { ref_key: RefKey { file_path: "../azure-rest-api-specs/specification/resources/resource-manager/Microsoft.Authorization/stable/2020-09-01/dataPolicyManifests.json", name: "CloudError"
-    ("security", "package-2020-01-preview-only"), // duplicate tag https://github.com/Azure/azure-rest-api-specs/pull/13828
-    ("synapse", "package-2019-06-01-preview"), // TODO #80 path parameters
-    ("synapse", "package-2020-12-01"),
-    ("synapse", "package-2021-03"),
-];
+/// The built-in skip lists and boxed properties
+const DEFAULT_GEN_CONFIG: &str = include_str!("gen_mgmt.toml");
 
-// becuse of recursive types, some properties have to be boxed
-// https://github.com/ctaggart/autorust/issues/73
-const BOX_PROPERTIES: &[(&str, &str, &str)] = &[
-    // cost-management
-    ("../azure-rest-api-specs/specification/cost-management/resource-manager/Microsoft.CostManagement/stable/2020-06-01/costmanagement.json", "ReportConfigFilter", "not"),
-    ("../azure-rest-api-specs/specification/cost-management/resource-manager/Microsoft.CostManagement/stable/2020-06-01/costmanagement.json", "QueryFilter", "not"),
-    // databox
-    ("../azure-rest-api-specs/specification/databox/resource-manager/Microsoft.DataBox/stable/2020-11-01/databox.json", "transferFilterDetails", "include"),
-    ("../azure-rest-api-specs/specification/databox/resource-manager/Microsoft.DataBox/stable/2020-11-01/databox.json", "transferAllDetails", "include"),
-    ("../azure-rest-api-specs/specification/databox/resource-manager/Microsoft.DataBox/stable/2021-03-01/databox.json", "transferFilterDetails", "include"),
-    ("../azure-rest-api-specs/specification/databox/resource-manager/Microsoft.DataBox/stable/2021-03-01/databox.json", "transferAllDetails", "include"),
-    // dataprotection
-    ("../azure-rest-api-specs/specification/dataprotection/resource-manager/Microsoft.DataProtection/stable/2021-01-01/dataprotection.json", "InnerError", "embeddedInnerError"),
-    // hardwaresecuritymodels
-    ("../azure-rest-api-specs/specification/hardwaresecuritymodules/resource-manager/Microsoft.HardwareSecurityModules/preview/2018-10-31-preview/dedicatedhsm.json", "Error", "innererror"),
-    // logic
-    ("../azure-rest-api-specs/specification/logic/resource-manager/Microsoft.Logic/stable/2019-05-01/logic.json", "SwaggerSchema", "items"),
-    // migrateprojects
-    ("../azure-rest-api-specs/specification/migrateprojects/resource-manager/Microsoft.Migrate/preview/2018-09-01-preview/migrate.json", "IEdmNavigationProperty", "partner"),
-    ("../azure-rest-api-specs/specification/migrateprojects/resource-manager/Microsoft.Migrate/preview/2018-09-01-preview/migrate.json", "IEdmStructuredType", "baseType"),
-    // network
-    ("../azure-rest-api-specs/specification/network/resource-manager/Microsoft.Network/stable/2020-07-01/publicIpAddress.json", "PublicIPAddressPropertiesFormat", "ipConfiguration"),
-    ("../azure-rest-api-specs/specification/network/resource-manager/Microsoft.Network/stable/2020-08-01/publicIpAddress.json", "PublicIPAddressPropertiesFormat", "ipConfiguration"),
-    ("../azure-rest-api-specs/specification/network/resource-manager/Microsoft.Network/stable/2020-11-01/publicIpAddress.json", "PublicIPAddressPropertiesFormat", "ipConfiguration"),
+const ONLY_SERVICES: &[&str] = &[
+    // "vmware",
+    // "network",
 ];
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -105,30 +46,128 @@ pub enum Error {
     LibRsError { source: lib_rs::Error },
     #[error("GetSpecFoldersError")]
     GetSpecFoldersError { source: autorust_codegen::Error },
+    #[error("GenConfigError")]
+    GenConfigError { source: gen_config::Error },
+    #[error("DefaultGenConfigError")]
+    DefaultGenConfigError { source: toml::de::Error },
+    #[error("--jobs {} is not a number", jobs)]
+    JobsError { jobs: String },
+    #[error("ThreadPoolError")]
+    ThreadPoolError { source: rayon::ThreadPoolBuildError },
+}
+
+/// What was generated
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenStats {
+    pub services: usize,
+    pub modules: usize,
+    pub types: usize,
+    pub operations: usize,
+    pub files: usize,
+    pub skipped_services: usize,
+    pub errors: usize,
+}
+
+impl GenStats {
+    fn add(&mut self, stats: &GenStats) {
+        self.services += stats.services;
+        self.modules += stats.modules;
+        self.types += stats.types;
+        self.operations += stats.operations;
+        self.files += stats.files;
+        self.skipped_services += stats.skipped_services;
+        self.errors += stats.errors;
+    }
 }
 
 fn main() -> Result<()> {
-    for (i, spec) in get_mgmt_configs()
+    let jobs = get_jobs()?;
+    let gen_config = match GenConfig::read(Path::new(GEN_CONFIG_FILE)).map_err(|source| Error::GenConfigError { source })? {
+        Some(gen_config) => gen_config,
+        None => GenConfig::parse(DEFAULT_GEN_CONFIG).map_err(|source| Error::DefaultGenConfigError { source })?,
+    };
+    let mut stats = GenStats::default();
+    let specs: Vec<SpecConfigs> = get_mgmt_configs()
         .map_err(|source| Error::GetSpecFoldersError { source })?
-        .iter()
-        .enumerate()
-    {
-        if ONLY_SERVICES.len() > 0 {
-            if ONLY_SERVICES.contains(&spec.spec()) {
-                println!("{} {}", i + 1, spec.spec());
-                gen_crate(spec)?;
+        .into_iter()
+        .filter(|spec| {
+            let skip = if ONLY_SERVICES.len() > 0 {
+                !ONLY_SERVICES.contains(&spec.spec())
+            } else {
+                gen_config.skip_service(spec.spec())
+            };
+            if skip {
+                stats.skipped_services += 1;
             }
-        } else {
-            if !SKIP_SERVICES.contains(&spec.spec()) {
-                println!("{} {}", i + 1, spec.spec());
-                gen_crate(spec)?;
+            !skip
+        })
+        .collect();
+
+    // the services are generated in parallel, and the errors reported after they all finish
+    let progress = ProgressBar::new(specs.len() as u64);
+    let crate_stats = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|source| Error::ThreadPoolError { source })?;
+    pool.install(|| {
+        specs.par_iter().for_each(|spec| {
+            progress.set_message(spec.spec().to_owned());
+            match gen_crate(spec, &gen_config) {
+                Ok(stats) => crate_stats.lock().unwrap().push((get_service_name(spec.spec()), stats)),
+                Err(error) => errors.lock().unwrap().push((spec.spec().to_owned(), error)),
             }
+            progress.inc(1);
+        })
+    });
+    progress.finish_and_clear();
+
+    let mut crate_stats = crate_stats.into_inner().unwrap();
+    crate_stats.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut service_names = Vec::new();
+    for (service_name, crate_stats) in crate_stats {
+        if crate_stats.services > 0 {
+            service_names.push(service_name);
         }
+        stats.add(&crate_stats);
+    }
+    let errors = errors.into_inner().unwrap();
+    stats.errors = errors.len();
+    for (service, error) in &errors {
+        eprintln!("ERROR {} {}", service, error);
     }
+    if CREATE_WORKSPACE && service_names.len() > 0 {
+        let crates: Vec<&str> = service_names.iter().map(String::as_str).collect();
+        cargo_toml::create_workspace(
+            &crates,
+            &path::join(OUTPUT_FOLDER, "Cargo.toml").map_err(|source| Error::PathError { source })?,
+        )
+        .map_err(|source| Error::CargoTomlError { source })?;
+        cargo_toml::create_rust_toolchain(&path::join(OUTPUT_FOLDER, "rust-toolchain.toml").map_err(|source| Error::PathError { source })?)
+            .map_err(|source| Error::CargoTomlError { source })?;
+    }
+    println!(
+        "generated {} services with {} modules, {} types, and {} operations, writing {} files; skipped {} services; {} errors",
+        stats.services, stats.modules, stats.types, stats.operations, stats.files, stats.skipped_services, stats.errors
+    );
     Ok(())
 }
 
-fn gen_crate(spec: &SpecConfigs) -> Result<()> {
+/// The number of services to generate at once, from `--jobs N`, which defaults to the number of CPUs
+fn get_jobs() -> Result<usize> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|arg| arg == "--jobs") {
+        Some(i) => {
+            let jobs = args.get(i + 1).map(String::as_str).unwrap_or_default();
+            jobs.parse().map_err(|_| Error::JobsError { jobs: jobs.to_owned() })
+        }
+        // zero lets rayon use the number of CPUs
+        None => Ok(0),
+    }
+}
+
+fn gen_crate(spec: &SpecConfigs, gen_config: &GenConfig) -> Result<GenStats> {
     let service_name = &get_service_name(spec.spec());
     let crate_name = &format!("azure_mgmt_{}", service_name);
     let output_folder = &path::join(OUTPUT_FOLDER, service_name).map_err(|source| Error::PathError { source })?;
@@ -138,26 +177,17 @@ fn gen_crate(spec: &SpecConfigs) -> Result<()> {
         fs::remove_dir_all(&src_folder).map_err(|source| Error::IoError { source })?;
     }
 
+    let mut stats = GenStats::default();
     let mut feature_mod_names = Vec::new();
-    let skip_service_tags: HashSet<&(&str, &str)> = SKIP_SERVICE_TAGS.iter().collect();
-
-    let mut box_properties = HashSet::new();
-    for (file_path, schema_name, property_name) in BOX_PROPERTIES {
-        box_properties.insert(PropertyName {
-            file_path: PathBuf::from(file_path),
-            schema_name: schema_name.to_string(),
-            property_name: property_name.to_string(),
-        });
-    }
+    let box_properties = gen_config.box_properties().map_err(|source| Error::GenConfigError { source })?;
 
     for config in spec.configs() {
         let tag = config.tag.as_str();
         if let Some(api_version) = to_api_version(&config) {
-            if skip_service_tags.contains(&(spec.spec(), tag)) {
+            if gen_config.skip_tag(spec.spec(), tag) {
                 // println!("  skipping {}", tag);
                 continue;
             }
-            println!("  {}", tag);
             // println!("  {}", api_version);
             let mod_name = &to_mod_name(tag);
             feature_mod_names.push((tag.to_string(), mod_name.clone()));
@@ -176,18 +206,26 @@ fn gen_crate(spec: &SpecConfigs) -> Result<()> {
             // for input_file in &input_files {
             //     println!("  {:?}", input_file);
             // }
-            autorust_codegen::run(Config {
+            let result = autorust_codegen::run(Config {
                 api_version: Some(api_version),
                 output_folder: mod_output_folder.into(),
                 input_files,
                 box_properties: box_properties.clone(),
+                ..Config::default()
             })
             .map_err(|source| Error::CodegenError { source })?;
+            if let RunResult::Written(written) = result {
+                stats.modules += 1;
+                stats.types += written.types;
+                stats.operations += written.operations;
+                stats.files += written.files.len();
+            }
         }
     }
     if feature_mod_names.len() == 0 {
-        return Ok(());
+        return Ok(stats);
     }
+    stats.services = 1;
     cargo_toml::create(
         crate_name,
         &feature_mod_names,
@@ -200,7 +238,7 @@ fn gen_crate(spec: &SpecConfigs) -> Result<()> {
     )
     .map_err(|source| Error::LibRsError { source })?;
 
-    Ok(())
+    Ok(stats)
 }
 
 fn get_service_name(spec_folder: &str) -> String {
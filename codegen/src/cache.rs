@@ -0,0 +1,123 @@
+//! A manifest of the hashes of the generated files, so that unchanged files are not written again.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the manifest in the output folder
+pub const CACHE_FILE_NAME: &str = ".autorust_cache";
+
+/// The key in the manifest for the hash of the input files
+const INPUT_KEY: &str = "input";
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not read input file {}: {}", file.display(), source)]
+    ReadInputFileError { file: PathBuf, source: std::io::Error },
+    #[error("Could not write cache {}: {}", file.display(), source)]
+    WriteCacheError { file: PathBuf, source: std::io::Error },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cache {
+    input_hash: String,
+    file_hashes: HashMap<String, String>,
+}
+
+impl Cache {
+    /// Read the manifest in the output folder, which is empty if it does not exist
+    pub fn read(output_folder: &Path) -> Self {
+        match fs::read_to_string(output_folder.join(CACHE_FILE_NAME)) {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut cache = Self::default();
+        for line in text.lines() {
+            if let Some((hash, name)) = line.split_once(' ') {
+                if name == INPUT_KEY {
+                    cache.input_hash = hash.to_owned();
+                } else {
+                    cache.file_hashes.insert(name.to_owned(), hash.to_owned());
+                }
+            }
+        }
+        cache
+    }
+
+    fn to_text(&self) -> String {
+        let mut names: Vec<&String> = self.file_hashes.keys().collect();
+        names.sort();
+        let mut text = format!("{} {}\n", self.input_hash, INPUT_KEY);
+        for name in names {
+            text.push_str(&format!("{} {}\n", self.file_hashes[name], name));
+        }
+        text
+    }
+
+    /// Write the manifest to the output folder
+    pub fn write(&self, output_folder: &Path) -> Result<()> {
+        let file = output_folder.join(CACHE_FILE_NAME);
+        fs::write(&file, self.to_text()).map_err(|source| Error::WriteCacheError { source, file })
+    }
+
+    /// Set the hash of the input files, which invalidates all the outputs if they changed
+    pub fn set_input_files<P: AsRef<Path>>(&mut self, input_files: &[P]) -> Result<()> {
+        let mut hasher = blake3::Hasher::new();
+        for input_file in input_files {
+            let file = input_file.as_ref();
+            let bytes = fs::read(file).map_err(|source| Error::ReadInputFileError {
+                source,
+                file: file.to_owned(),
+            })?;
+            hasher.update(file.to_string_lossy().as_bytes());
+            hasher.update(&bytes);
+        }
+        let input_hash = hasher.finalize().to_hex().to_string();
+        if input_hash != self.input_hash {
+            self.input_hash = input_hash;
+            self.file_hashes.clear();
+        }
+        Ok(())
+    }
+
+    /// Records the hash of the code for the file, returning false if it is unchanged
+    pub fn update(&mut self, file: &Path, code: &str) -> bool {
+        let name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let hash = blake3::hash(code.as_bytes()).to_hex().to_string();
+        if file.exists() && self.file_hashes.get(&name) == Some(&hash) {
+            return false;
+        }
+        self.file_hashes.insert(name, hash);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trip() {
+        let mut cache = Cache::default();
+        cache.input_hash = "abc".to_owned();
+        cache.file_hashes.insert("models.rs".to_owned(), "def".to_owned());
+        cache.file_hashes.insert("operations.rs".to_owned(), "ghi".to_owned());
+        let text = cache.to_text();
+        assert_eq!(text, "abc input\ndef models.rs\nghi operations.rs\n");
+        assert_eq!(Cache::parse(&text), cache);
+    }
+
+    #[test]
+    fn test_update_new_file() {
+        let mut cache = Cache::default();
+        assert!(cache.update(Path::new("does/not/exist/models.rs"), "pub struct Foo {}"));
+        assert!(cache.file_hashes.contains_key("models.rs"));
+    }
+}
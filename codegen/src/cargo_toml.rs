@@ -11,35 +11,144 @@ pub enum Error {
     IoError { source: std::io::Error },
 }
 
+/// The versions of the dependencies of a generated crate
+#[derive(Clone, Debug, PartialEq)]
+pub struct CargoTomlConfig {
+    pub azure_core_version: String,
+    pub azure_identity_version: String,
+    pub serde_version: String,
+    pub serde_json_version: String,
+    pub serde_urlencoded_version: String,
+    pub serde_with_version: String,
+    pub reqwest_version: String,
+    pub bytes_version: String,
+    pub futures_version: String,
+    pub futures_timer_version: String,
+    pub thiserror_version: String,
+    pub http_version: String,
+    pub url_version: String,
+    pub chrono_version: String,
+    pub uuid_version: String,
+    pub serde_xml_rs_version: String,
+    pub tokio_version: String,
+}
+
+impl Default for CargoTomlConfig {
+    fn default() -> Self {
+        Self {
+            azure_core_version: "0.1.0".to_owned(),
+            azure_identity_version: "0.1.0".to_owned(),
+            serde_version: "1.0".to_owned(),
+            serde_json_version: "1.0".to_owned(),
+            serde_urlencoded_version: "0.7".to_owned(),
+            serde_with_version: "3".to_owned(),
+            reqwest_version: "0.11".to_owned(),
+            bytes_version: "1.0".to_owned(),
+            futures_version: "0.3".to_owned(),
+            futures_timer_version: "3.0".to_owned(),
+            thiserror_version: "1.0".to_owned(),
+            http_version: "0.2".to_owned(),
+            url_version: "2.2".to_owned(),
+            chrono_version: "0.4".to_owned(),
+            uuid_version: "1".to_owned(),
+            serde_xml_rs_version: "0.5".to_owned(),
+            tokio_version: "1.0".to_owned(),
+        }
+    }
+}
+
 pub fn create(crate_name: &str, feature_mod_names: &Vec<(String, String)>, path: &Path) -> Result<()> {
+    create_with_config(crate_name, feature_mod_names, path, &CargoTomlConfig::default())
+}
+
+pub fn create_with_config(
+    crate_name: &str,
+    feature_mod_names: &Vec<(String, String)>,
+    path: &Path,
+    config: &CargoTomlConfig,
+) -> Result<()> {
+    let azure_core = format!(r#"{{ path = "../../../sdk/core", version = "{}" }}"#, config.azure_core_version);
+    create_file(crate_name, feature_mod_names, path, config, &azure_core)
+}
+
+/// Creates a Cargo.toml that depends on a published `azure_core`, instead of the one in the azure-sdk-for-rust repo
+pub fn create_with_azure_core(
+    crate_name: &str,
+    feature_mod_names: &Vec<(String, String)>,
+    azure_core_version: &str,
+    path: &Path,
+) -> Result<()> {
+    let config = CargoTomlConfig {
+        azure_core_version: azure_core_version.to_owned(),
+        ..CargoTomlConfig::default()
+    };
+    let azure_core = format!(r#"{{ version = "{}" }}"#, config.azure_core_version);
+    create_file(crate_name, feature_mod_names, path, &config, &azure_core)
+}
+
+fn create_file(
+    crate_name: &str,
+    feature_mod_names: &Vec<(String, String)>,
+    path: &Path,
+    config: &CargoTomlConfig,
+    azure_core: &str,
+) -> Result<()> {
     let file = File::create(path).map_err(|source| Error::IoError { source })?;
     let mut file = LineWriter::new(file);
     let version = &env!("CARGO_PKG_VERSION");
     file.write_all(
         format!(
-            r#"# generated by AutoRust {}
+            r#"# generated by AutoRust {version}
 [package]
-name = "{}"
+name = "{crate_name}"
 version = "0.1.0"
 edition = "2018"
 
 [dependencies]
-azure_core = {{ path = "../../../sdk/core", version = "0.1.0" }}
-serde = {{ version = "1.0", features = ["derive"] }}
-serde_json = "1.0"
-reqwest = {{ version = "0.11", features = ["json"] }}
-bytes = "1.0"
-thiserror = "1.0"
-http = "0.2"
-url = "2.2"
+azure_core = {azure_core}
+serde = {{ version = "{serde}", features = ["derive"] }}
+serde_json = "{serde_json}"
+serde_urlencoded = "{serde_urlencoded}"
+serde_with = {{ version = "{serde_with}", features = ["base64"] }}
+reqwest = {{ version = "{reqwest}", features = ["json"] }}
+bytes = "{bytes}"
+futures = "{futures}"
+futures-timer = "{futures_timer}"
+thiserror = "{thiserror}"
+http = "{http}"
+url = "{url}"
+chrono = {{ version = "{chrono}", features = ["serde"], optional = true }}
+uuid = {{ version = "{uuid}", features = ["serde", "v4"], optional = true }}
+serde-xml-rs = {{ version = "{serde_xml_rs}", optional = true }}
 
 [dev-dependencies]
-azure_identity = {{ path = "../../../sdk/identity", version = "0.1.0" }}
-tokio = {{ version = "1.0", features = ["macros"] }}
+azure_identity = {{ path = "../../../sdk/identity", version = "{azure_identity}" }}
+tokio = {{ version = "{tokio}", features = ["macros"] }}
 
 [features]
+dates = ["chrono"]
+uuids = ["uuid"]
+xml = ["serde-xml-rs"]
 "#,
-            version, crate_name
+            version = version,
+            crate_name = crate_name,
+            azure_core = azure_core,
+            serde = config.serde_version,
+            serde_json = config.serde_json_version,
+            serde_urlencoded = config.serde_urlencoded_version,
+            serde_with = config.serde_with_version,
+            reqwest = config.reqwest_version,
+            bytes = config.bytes_version,
+            futures = config.futures_version,
+            futures_timer = config.futures_timer_version,
+            thiserror = config.thiserror_version,
+            http = config.http_version,
+            url = config.url_version,
+            chrono = config.chrono_version,
+            uuid = config.uuid_version,
+            serde_xml_rs = config.serde_xml_rs_version,
+            azure_identity = config.azure_identity_version,
+            tokio = config.tokio_version,
         )
         .as_bytes(),
     )
@@ -56,6 +165,29 @@ tokio = {{ version = "1.0", features = ["macros"] }}
     Ok(())
 }
 
+/// The stable toolchain used to build the generated workspace
+pub const RUST_TOOLCHAIN_CHANNEL: &str = "1.64.0";
+
+/// Creates a workspace Cargo.toml with the generated crates as members
+pub fn create_workspace(crates: &[&str], path: &Path) -> Result<()> {
+    let file = File::create(path).map_err(|source| Error::IoError { source })?;
+    let mut file = LineWriter::new(file);
+    let version = &env!("CARGO_PKG_VERSION");
+    file.write_all(format!("# generated by AutoRust {}\n[workspace]\nmembers = [\n", version).as_bytes())
+        .map_err(|source| Error::IoError { source })?;
+    for crate_path in crates {
+        file.write_all(format!("    \"{}\",\n", crate_path).as_bytes())
+            .map_err(|source| Error::IoError { source })?;
+    }
+    file.write_all(b"]\n").map_err(|source| Error::IoError { source })?;
+    Ok(())
+}
+
+/// Creates a rust-toolchain.toml that pins the toolchain of the generated workspace
+pub fn create_rust_toolchain(path: &Path) -> Result<()> {
+    std::fs::write(path, format!("[toolchain]\nchannel = \"{}\"\n", RUST_TOOLCHAIN_CHANNEL)).map_err(|source| Error::IoError { source })
+}
+
 fn get_default_feature(feature_mod_names: &Vec<(String, String)>) -> String {
     let default = feature_mod_names
         .iter()
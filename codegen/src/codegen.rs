@@ -1,15 +1,16 @@
 use crate::{
-    identifier::{self, ident, CamelCaseIdent},
-    spec,
+    identifier::{self, ident, to_field_name, to_module_name, to_type_name, CamelCaseIdent},
+    scc, spec,
     status_codes::{get_error_responses, get_response_type_name, get_status_code_name, get_success_responses, has_default_response},
     Config, OperationVerb, PropertyName, ResolvedSchema, Spec,
 };
 use autorust_openapi::{
-    CollectionFormat, DataType, Parameter, ParameterType, PathItem, Reference, ReferenceOr, Response, Schema, SchemaCommon,
+    AdditionalProperties, CollectionFormat, DataType, Operation, Parameter, ParameterType, Reference, ReferenceOr, Response, Schema,
+    SchemaCommon,
 };
 use heck::{CamelCase, SnakeCase};
-use indexmap::IndexMap;
-use proc_macro2::TokenStream;
+use indexmap::{IndexMap, IndexSet};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Span, TokenStream, TokenTree};
 use quote::quote;
 use regex::Regex;
 use serde_json::Value;
@@ -23,12 +24,25 @@ use std::{
 pub struct CodeGen {
     config: Config,
     pub spec: Spec,
+    /// The type names of the schemas of all the docs, which a struct builder must not take
+    schema_type_names: HashSet<String>,
 }
 
 impl CodeGen {
     pub fn new(config: Config) -> Result<Self> {
         let spec = Spec::read_files(&config.input_files).map_err(|source| Error::SpecError { source })?;
-        Ok(Self { config, spec })
+        let schema_type_names = spec
+            .docs()
+            .values()
+            .flat_map(|doc| doc.definitions.keys())
+            .filter_map(|name| to_type_name(name).ok())
+            .map(|name| name.to_string())
+            .collect();
+        Ok(Self {
+            config,
+            spec,
+            schema_type_names,
+        })
     }
 
     pub fn input_files(&self) -> &[PathBuf] {
@@ -49,7 +63,81 @@ impl CodeGen {
         file.extend(quote! {
             #![allow(non_camel_case_types)]
             #![allow(unused_imports)]
+            #![allow(deprecated)]
             use serde::{Deserialize, Serialize};
+            use std::collections::HashMap;
+
+            #[cfg(feature = "dates")]
+            pub type DateTime = chrono::DateTime<chrono::Utc>;
+            #[cfg(not(feature = "dates"))]
+            #[doc = "RFC3339 date-time string"]
+            pub type DateTime = String;
+
+            #[doc = "Serializes a date-time as RFC3339, and also deserializes one without an offset as UTC"]
+            pub struct Rfc3339;
+            #[cfg(feature = "dates")]
+            impl serde_with::SerializeAs<DateTime> for Rfc3339 {
+                fn serialize_as<S: serde::Serializer>(value: &DateTime, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                    serializer.serialize_str(&value.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+                }
+            }
+            #[cfg(feature = "dates")]
+            impl<'de> serde_with::DeserializeAs<'de, DateTime> for Rfc3339 {
+                fn deserialize_as<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<DateTime, D::Error> {
+                    let value = String::deserialize(deserializer)?;
+                    chrono::DateTime::parse_from_rfc3339(&value)
+                        .map(|value| value.with_timezone(&chrono::Utc))
+                        .or_else(|_| {
+                            chrono::NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f")
+                                .map(|value| chrono::DateTime::from_utc(value, chrono::Utc))
+                        })
+                        .map_err(serde::de::Error::custom)
+                }
+            }
+            #[cfg(not(feature = "dates"))]
+            impl serde_with::SerializeAs<DateTime> for Rfc3339 {
+                fn serialize_as<S: serde::Serializer>(value: &DateTime, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                    serializer.serialize_str(value)
+                }
+            }
+            #[cfg(not(feature = "dates"))]
+            impl<'de> serde_with::DeserializeAs<'de, DateTime> for Rfc3339 {
+                fn deserialize_as<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<DateTime, D::Error> {
+                    String::deserialize(deserializer)
+                }
+            }
+            #[cfg(feature = "uuids")]
+            pub use uuid::Uuid;
+            #[cfg(not(feature = "uuids"))]
+            #[doc = "UUID string"]
+            pub type Uuid = String;
+
+            #[derive(Debug, thiserror::Error)]
+            pub enum BuilderError {
+                #[error("Required field {} is not set", field)]
+                MissingField { field: &'static str },
+            }
+
+            #[derive(Debug, thiserror::Error)]
+            #[error("Unknown enum value {}", value)]
+            pub struct ParseError {
+                pub value: String,
+            }
+
+            #[doc = "Binary data that is base64 encoded in JSON"]
+            #[serde_with::serde_as]
+            #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+            pub struct Bytes(#[serde_as(as = "serde_with::base64::Base64")] pub Vec<u8>);
+            impl From<Vec<u8>> for Bytes {
+                fn from(bytes: Vec<u8>) -> Self {
+                    Self(bytes)
+                }
+            }
+            impl AsRef<[u8]> for Bytes {
+                fn as_ref(&self) -> &[u8] {
+                    &self.0
+                }
+            }
         });
         let mut all_schemas: IndexMap<RefKey, ResolvedSchema> = IndexMap::new();
 
@@ -77,6 +165,21 @@ impl CodeGen {
             }
         }
 
+        let mut box_properties = self.get_recursive_box_properties(&all_schemas)?;
+        // the properties of a discriminated schema are in its base struct
+        for prop_nm in box_properties.clone() {
+            let is_discriminated = all_schemas.iter().any(|(ref_key, schema)| {
+                schema.schema.discriminator.is_some() && ref_key.file_path == prop_nm.file_path && ref_key.name == prop_nm.schema_name
+            });
+            if is_discriminated {
+                box_properties.insert(PropertyName {
+                    schema_name: get_discriminated_base_name(&prop_nm.schema_name),
+                    ..prop_nm
+                });
+            }
+        }
+
+        // schemas with the same name are identical, since different ones were renamed when the spec was read
         let mut schema_names = IndexMap::new();
         for (ref_key, schema) in &all_schemas {
             let doc_file = &ref_key.file_path;
@@ -89,32 +192,69 @@ impl CodeGen {
             } else {
                 if is_array(&schema.schema.common) {
                     file.extend(self.create_vec_alias(doc_file, schema_name, schema)?);
+                } else if is_model_as_string(schema) {
+                    file.extend(self.create_string_alias(schema_name)?);
                 } else if is_local_enum(schema) {
                     let no_namespace = TokenStream::new();
                     let (_tp_name, tp) = create_enum(&no_namespace, schema_name, schema)?;
                     file.extend(tp);
+                } else if schema.schema.discriminator.is_some() {
+                    for stream in self.create_discriminated_enum(&all_schemas, ref_key, schema, &box_properties)? {
+                        file.extend(stream);
+                    }
                 } else {
-                    for stream in self.create_struct(doc_file, schema_name, schema)? {
+                    for stream in self.create_struct(doc_file, schema_name, schema, &box_properties)? {
                         file.extend(stream);
                     }
                 }
             }
         }
-        Ok(file)
+        // the local types modules import what they use of the models by name
+        let mut model_names = get_item_names(&file);
+        model_names.extend(["Deserialize", "Serialize", "HashMap"].iter().map(|name| name.to_string()));
+        let models_path = |depth: usize| {
+            let supers = (0..depth).map(|_| quote! { super });
+            quote! { #(#supers)::* }
+        };
+        Ok(add_model_imports(file, &model_names, &models_path, 0))
     }
 
-    pub fn create_operations(&self) -> Result<TokenStream> {
+    /// Creates the operations, which import the `model_names` that they use from the models
+    pub fn create_operations(&self, model_names: &IndexSet<String>) -> Result<TokenStream> {
         let mut file = TokenStream::new();
         file.extend(create_generated_by_header());
         file.extend(quote! {
             #![allow(unused_mut)]
             #![allow(unused_variables)]
             #![allow(unused_imports)]
-            use crate::models::*;
-
+            #![allow(deprecated)]
+        });
+        let mut body = TokenStream::new();
+        if self.config.aliased_config_type {
+            body.extend(quote! {
+                pub type Configuration = crate::OperationConfig;
+            });
+        }
+        body.extend(quote! {
+            #[doc = "A client for the operations, which calls them with its config"]
+            #[derive(Clone)]
+            pub struct Client {
+                config: std::sync::Arc<crate::OperationConfig>,
+            }
+            impl Client {
+                pub fn new(config: crate::OperationConfig) -> Self {
+                    Self {
+                        config: std::sync::Arc::new(config),
+                    }
+                }
+                pub fn config(&self) -> &crate::OperationConfig {
+                    &self.config
+                }
+            }
         });
         let param_re = Regex::new(r"\{(\w+)\}").unwrap();
-        let mut modules: IndexMap<Option<String>, TokenStream> = IndexMap::new();
+        // the functions and the client methods of each module
+        let mut modules: IndexMap<Option<String>, (TokenStream, TokenStream)> = IndexMap::new();
         // println!("input_files {:?}", self.input_files());
         for (doc_file, doc) in self.spec.docs() {
             // only operations from listed input files
@@ -126,24 +266,18 @@ impl CodeGen {
                     .map_err(|source| Error::SpecError { source })?;
                 for (path, item) in &paths {
                     for op in spec::path_item_operations(item) {
-                        let (module_name, function_name) = op.function_name(path);
-                        let function = create_function(self, doc_file, path, item, &op, &param_re, &function_name)?;
-                        if modules.contains_key(&module_name) {}
-                        match modules.get_mut(&module_name) {
-                            Some(module) => {
-                                module.extend(function);
-                            }
-                            None => {
-                                let mut module = TokenStream::new();
-                                module.extend(function);
-                                modules.insert(module_name, module);
-                            }
-                        }
+                        let (module_name, function_name) = op.module_and_function_name(path);
+                        let (function, methods) = create_function(self, doc_file, path, &op, &param_re, &function_name)?;
+                        let module = modules
+                            .entry(module_name)
+                            .or_insert_with(|| (TokenStream::new(), TokenStream::new()));
+                        module.0.extend(function);
+                        module.1.extend(methods);
                     }
                 }
             }
         }
-        for (module_name, module) in modules {
+        for (module_name, (module, methods)) in modules {
             match module_name {
                 Some(module_name) => {
                     let name = ident(&module_name).map_err(|source| Error::IdentError {
@@ -151,16 +285,133 @@ impl CodeGen {
                         file: file!(),
                         line: line!(),
                     })?;
-                    file.extend(quote! {
+                    let client = to_type_name(&format!("{}_client", module_name)).map_err(|source| Error::IdentError {
+                        source,
+                        file: file!(),
+                        line: line!(),
+                    })?;
+                    let doc = format!("A client for the `{}` operations", module_name);
+                    body.extend(quote! {
                         pub mod #name {
-                            use crate::models::*;
-
                             #module
+                            #[doc = #doc]
+                            #[derive(Clone)]
+                            pub struct #client {
+                                config: std::sync::Arc<crate::OperationConfig>,
+                            }
+                            impl #client {
+                                pub(crate) fn new(config: std::sync::Arc<crate::OperationConfig>) -> Self {
+                                    Self { config }
+                                }
+                                #methods
+                            }
+                        }
+                        pub use #name::*;
+                        impl Client {
+                            pub fn #name(&self) -> #name::#client {
+                                #name::#client::new(self.config.clone())
+                            }
                         }
                     });
                 }
                 None => {
-                    file.extend(module);
+                    body.extend(quote! {
+                        #module
+                        impl Client {
+                            #methods
+                        }
+                    });
+                }
+            }
+        }
+        // the models are imported by name instead of with a glob, in the file and each module
+        let models_path = |_depth: usize| quote! { crate::models };
+        file.extend(create_model_imports(&body, model_names, &models_path(0)));
+        file.extend(add_model_imports(body, model_names, &models_path, 0));
+        Ok(file)
+    }
+
+    /// Creates tests from the `x-ms-examples` of the operations, which check that the
+    /// example request and response bodies round-trip through the models.
+    pub fn create_examples(&self) -> Result<TokenStream> {
+        let mut file = TokenStream::new();
+        file.extend(create_generated_by_header());
+        file.extend(quote! {
+            #![allow(unused_imports)]
+            #![allow(deprecated)]
+            use super::models;
+            use super::models::*;
+        });
+        let mut test_names = HashSet::new();
+        for (doc_file, doc) in self.spec.input_docs() {
+            let paths = self
+                .spec
+                .resolve_path_map(doc_file, &doc.paths)
+                .map_err(|source| Error::SpecError { source })?;
+            for (path, item) in &paths {
+                for op in spec::path_item_operations(item) {
+                    let (_, function_name) = op.function_name(path);
+                    let operation = op.operation();
+                    let parameters = self
+                        .spec
+                        .resolve_parameters(doc_file, &operation.parameters)
+                        .map_err(|source| Error::SpecError { source })?;
+                    for (example_name, example) in &operation.x_ms_examples {
+                        let example_path = match example {
+                            ReferenceOr::Reference {
+                                reference: Reference { file: Some(file), .. },
+                                ..
+                            } => spec::join_reference_file(doc_file, file).map_err(|source| Error::SpecError { source })?,
+                            _ => continue,
+                        };
+                        let example = match read_example(&example_path) {
+                            Some(example) => example,
+                            None => continue,
+                        };
+                        let mut bodies = Vec::new();
+                        for param in &parameters {
+                            if let (ParameterType::Body, Some(schema)) = (&param.in_, &param.schema) {
+                                if let Some(body) = example.pointer(&format!("/parameters/{}", param.name)) {
+                                    if !is_binary_ref(schema) {
+                                        bodies.push((get_type_name_for_schema_ref(schema, AsReference::False)?, body));
+                                    }
+                                }
+                            }
+                        }
+                        for (status_code, rsp) in &get_success_responses(&operation.responses) {
+                            let status_code = match status_code {
+                                autorust_openapi::StatusCode::Code(status_code) => status_code,
+                                autorust_openapi::StatusCode::Default => continue,
+                            };
+                            let body = example.pointer(&format!("/responses/{}/body", status_code));
+                            if let (Some(schema), Some(body)) = (&rsp.schema, body) {
+                                if !is_binary_ref(schema) {
+                                    bodies.push((get_type_name_for_schema_ref(schema, AsReference::False)?, body));
+                                }
+                            }
+                        }
+                        for (i, (tp, body)) in bodies.into_iter().enumerate() {
+                            let mut test_name = format!("example_{}_{}_{}", function_name, example_name.to_snake_case(), i);
+                            while !test_names.insert(test_name.clone()) {
+                                test_name.push('_');
+                            }
+                            let test_name = ident(&test_name).map_err(|source| Error::IdentError {
+                                source,
+                                file: file!(),
+                                line: line!(),
+                            })?;
+                            let json = body.to_string();
+                            file.extend(quote! {
+                                #[tokio::test]
+                                async fn #test_name() -> std::result::Result<(), serde_json::Error> {
+                                    let value: #tp = serde_json::from_str(#json)?;
+                                    let round_trip: #tp = serde_json::from_value(serde_json::to_value(&value)?)?;
+                                    assert_eq!(value, round_trip);
+                                    Ok(())
+                                }
+                            });
+                        }
+                    }
                 }
             }
         }
@@ -173,23 +424,207 @@ impl CodeGen {
             .spec
             .resolve_schema_ref(doc_file, schema_ref)
             .map_err(|source| Error::SpecError { source })?;
+        self.add_resolved_schema(schemas, schema)
+    }
+
+    // For create_models. Adds a schema and recursively its refs.
+    // For a discriminated schema, the schemas in the same file with it in their allOf are added too.
+    fn add_resolved_schema(&self, schemas: &mut IndexMap<RefKey, ResolvedSchema>, schema: ResolvedSchema) -> Result<()> {
         if let Some(ref_key) = schema.ref_key.clone() {
             if !schemas.contains_key(&ref_key) {
                 if !self.spec.is_input_file(&ref_key.file_path) {
                     let refs = get_schema_schema_references(&schema.schema);
+                    let is_discriminated = schema.schema.discriminator.is_some();
                     schemas.insert(ref_key.clone(), schema);
                     for reference in refs {
                         self.add_schema_refs(schemas, &ref_key.file_path, reference)?;
                     }
+                    if is_discriminated {
+                        if let Some(doc) = self.spec.docs().get(&ref_key.file_path) {
+                            for (name, definition) in &doc.definitions {
+                                if let ReferenceOr::Item(definition) = definition {
+                                    if self.get_all_of_ref_keys(&ref_key.file_path, definition)?.contains(&ref_key) {
+                                        let subtype = ResolvedSchema {
+                                            ref_key: Some(RefKey {
+                                                file_path: ref_key.file_path.clone(),
+                                                name: name.clone(),
+                                            }),
+                                            schema: definition.clone(),
+                                        };
+                                        self.add_resolved_schema(schemas, subtype)?;
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
             }
         }
         Ok(())
     }
 
+    /// Gets the keys of the referenced schemas in the allOf of a schema.
+    fn get_all_of_ref_keys(&self, doc_file: &Path, schema: &Schema) -> Result<Vec<RefKey>> {
+        Ok(self
+            .spec
+            .resolve_schemas(doc_file, &schema.all_of)
+            .map_err(|source| Error::SpecError { source })?
+            .into_iter()
+            .filter_map(|all_of| all_of.ref_key)
+            .collect())
+    }
+
+    /// Gets the schemas with a discriminated schema in their allOf, along with their discriminator values.
+    fn get_discriminated_subtypes(
+        &self,
+        all_schemas: &IndexMap<RefKey, ResolvedSchema>,
+        base_ref_key: &RefKey,
+    ) -> Result<Vec<(RefKey, String)>> {
+        let mut subtypes = Vec::new();
+        for (ref_key, schema) in all_schemas {
+            if self.get_all_of_ref_keys(&ref_key.file_path, &schema.schema)?.contains(base_ref_key) {
+                let value = schema
+                    .schema
+                    .x_ms_discriminator_value
+                    .clone()
+                    .unwrap_or_else(|| ref_key.name.clone());
+                subtypes.push((ref_key.clone(), value));
+            }
+        }
+        Ok(subtypes)
+    }
+
+    /// Creates an enum tagged with the discriminator, with a variant for each subtype.
+    /// The properties of the discriminated schema are in a base struct, which the subtypes flatten.
+    fn create_discriminated_enum(
+        &self,
+        all_schemas: &IndexMap<RefKey, ResolvedSchema>,
+        ref_key: &RefKey,
+        schema: &ResolvedSchema,
+        box_properties: &HashSet<PropertyName>,
+    ) -> Result<Vec<TokenStream>> {
+        let discriminator = schema.schema.discriminator.as_deref().unwrap_or_default();
+        let base_name = get_discriminated_base_name(&ref_key.name);
+        let mut base = ResolvedSchema {
+            ref_key: schema.ref_key.clone(),
+            schema: schema.schema.clone(),
+        };
+        // the discriminator is the enum tag
+        base.schema.properties.shift_remove(discriminator);
+        base.schema.required.retain(|required| required != discriminator);
+        let mut streams = self.create_struct(&ref_key.file_path, &base_name, &base, box_properties)?;
+
+        let nm = to_type_name(&ref_key.name).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        let base_nm = to_type_name(&base_name).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        let subtypes = self.get_discriminated_subtypes(all_schemas, ref_key)?;
+        if subtypes.len() == 0 {
+            streams.push(quote! { pub type #nm = #base_nm; });
+            return Ok(streams);
+        }
+        let mut variants = TokenStream::new();
+        for (subtype, value) in subtypes {
+            let variant = to_type_name(&subtype.name).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            variants.extend(quote! {
+                #[serde(rename = #value)]
+                #variant(#variant),
+            });
+        }
+        streams.push(quote! {
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(tag = #discriminator)]
+            pub enum #nm {
+                #variants
+            }
+        });
+        Ok(streams)
+    }
+
+    /// Finds the properties that have to be boxed because of recursive types.
+    ///
+    /// Within each cycle of schemas that contain each other, the optional properties are boxed first.
+    /// If that does not break the cycle, the rest of its properties are boxed too.
+    fn get_recursive_box_properties(&self, all_schemas: &IndexMap<RefKey, ResolvedSchema>) -> Result<HashSet<PropertyName>> {
+        // the properties and flattened allOf schemas that are contained by value
+        let mut edges = Vec::new();
+        for (ref_key, schema) in all_schemas {
+            let properties = self
+                .spec
+                .resolve_schema_map(&ref_key.file_path, &schema.schema.properties)
+                .map_err(|source| Error::SpecError { source })?;
+            for (property_name, property) in properties {
+                if let Some(target) = property.ref_key {
+                    let is_required = schema.schema.required.contains(&property_name);
+                    let property_name = PropertyName {
+                        file_path: ref_key.file_path.clone(),
+                        schema_name: ref_key.name.clone(),
+                        property_name,
+                    };
+                    edges.push((ref_key.clone(), target, Some((property_name, is_required))));
+                }
+            }
+            for target in self.get_all_of_ref_keys(&ref_key.file_path, &schema.schema)? {
+                edges.push((ref_key.clone(), target, None));
+            }
+            // a discriminated schema is an enum that contains its subtypes
+            if schema.schema.discriminator.is_some() {
+                for (target, _) in self.get_discriminated_subtypes(all_schemas, ref_key)? {
+                    edges.push((ref_key.clone(), target, None));
+                }
+            }
+        }
+
+        let mut box_properties = HashSet::new();
+        for optional_only in &[true, false] {
+            let mut graph = scc::Graph::new();
+            for ref_key in all_schemas.keys() {
+                graph.insert(ref_key.clone(), IndexSet::new());
+            }
+            for (from, to, property) in &edges {
+                let is_boxed = property
+                    .as_ref()
+                    .map_or(false, |(property_name, _)| box_properties.contains(property_name));
+                if !is_boxed {
+                    graph.entry(from.clone()).or_insert_with(IndexSet::new).insert(to.clone());
+                }
+            }
+            for cycle in scc::cycles(&graph) {
+                let cycle: HashSet<_> = cycle.into_iter().collect();
+                for (from, to, property) in &edges {
+                    if let Some((property_name, is_required)) = property {
+                        if cycle.contains(from) && cycle.contains(to) && !(*optional_only && *is_required) {
+                            box_properties.insert(property_name.clone());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(box_properties)
+    }
+
+    fn create_string_alias(&self, alias_name: &str) -> Result<TokenStream> {
+        let typ = to_type_name(&alias_name).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        Ok(quote! { pub type #typ = String; })
+    }
+
     fn create_vec_alias(&self, _doc_file: &Path, alias_name: &str, schema: &ResolvedSchema) -> Result<TokenStream> {
         let items = get_schema_array_items(&schema.schema.common)?;
-        let typ = ident(&alias_name.to_camel_case()).map_err(|source| Error::IdentError {
+        let typ = to_type_name(&alias_name).map_err(|source| Error::IdentError {
             source,
             file: file!(),
             line: line!(),
@@ -198,57 +633,188 @@ impl CodeGen {
         Ok(quote! { pub type #typ = Vec<#items_typ>; })
     }
 
-    fn create_struct(&self, doc_file: &Path, struct_name: &str, schema: &ResolvedSchema) -> Result<Vec<TokenStream>> {
+    fn create_struct(
+        &self,
+        doc_file: &Path,
+        struct_name: &str,
+        schema: &ResolvedSchema,
+        box_properties: &HashSet<PropertyName>,
+    ) -> Result<Vec<TokenStream>> {
         // println!("create_struct {} {}", doc_file.to_str().unwrap(), struct_name);
         let mut streams = Vec::new();
         let mut local_types = Vec::new();
         let mut props = TokenStream::new();
-        let ns = ident(&struct_name.to_snake_case()).map_err(|source| Error::IdentError {
+        let ns = to_module_name(&struct_name).map_err(|source| Error::IdentError {
             source,
             file: file!(),
             line: line!(),
         })?;
-        let nm = ident(&struct_name.to_camel_case()).map_err(|source| Error::IdentError {
+        let nm = to_type_name(&struct_name).map_err(|source| Error::IdentError {
             source,
             file: file!(),
             line: line!(),
         })?;
         let required: HashSet<&str> = schema.schema.required.iter().map(String::as_str).collect();
+        let mut has_serde_as = false;
+        let mut fields = Vec::new();
+        let mut flattened = Vec::new();
+        let mut default_fns = TokenStream::new();
+        let struct_type_name = nm.to_string();
+        // a flattened allOf struct and additional properties maps are not Ord
+        let mut all_ord = schema.schema.all_of.len() != 1;
 
-        for schema in &schema.schema.all_of {
-            let type_name = get_type_name_for_schema_ref(schema, AsReference::False)?;
-            let field_name = ident(&type_name.to_string().to_snake_case()).map_err(|source| Error::IdentError {
-                source,
-                file: file!(),
-                line: line!(),
-            })?;
-            props.extend(quote! {
-                #[serde(flatten)]
-                pub #field_name: #type_name,
-            });
+        // the fields of multiple bases are composed, since a struct can only flatten one without field name conflicts
+        let is_composed = schema.schema.all_of.len() > 1;
+        let mut properties = IndexMap::new();
+        let mut base_field = None;
+        if is_composed {
+            for base in &schema.schema.all_of {
+                self.collect_composed_properties(doc_file, base, &mut properties)?;
+            }
+        } else {
+            for schema in &schema.schema.all_of {
+                let resolved = self
+                    .spec
+                    .resolve_schema(doc_file, schema)
+                    .map_err(|source| Error::SpecError { source })?;
+                let type_name = match &resolved.ref_key {
+                    // a discriminated schema is an enum, so its base struct is flattened instead
+                    Some(ref_key) if resolved.schema.discriminator.is_some() => {
+                        ident(&get_discriminated_base_name(&ref_key.name).to_camel_case()).map_err(|source| Error::IdentError {
+                            source,
+                            file: file!(),
+                            line: line!(),
+                        })?
+                    }
+                    _ => get_type_name_for_schema_ref(schema, AsReference::False)?,
+                };
+                let field_name = ident(&type_name.to_string().to_snake_case()).map_err(|source| Error::IdentError {
+                    source,
+                    file: file!(),
+                    line: line!(),
+                })?;
+                props.extend(quote! {
+                    #[serde(flatten)]
+                    pub #field_name: #type_name,
+                });
+                base_field = Some((field_name.clone(), type_name.clone()));
+                fields.push(StructField {
+                    name: field_name,
+                    tp: type_name,
+                    is_required: true,
+                });
+            }
         }
 
-        let properties = self
+        let own_properties = self
             .spec
             .resolve_schema_map(doc_file, &schema.schema.properties)
             .map_err(|source| Error::SpecError { source })?;
-        for (property_name, property) in &properties {
-            let nm = ident(&property_name.to_snake_case()).map_err(|source| Error::IdentError {
+        for (property_name, property) in own_properties {
+            let is_required = required.contains(property_name.as_str());
+            properties.insert(
+                property_name,
+                ComposedProperty {
+                    doc_file: doc_file.to_owned(),
+                    schema: property,
+                    is_required,
+                    base: None,
+                },
+            );
+        }
+        // the required properties may be inherited
+        for (property_name, composed) in properties.iter_mut() {
+            composed.is_required = composed.is_required || required.contains(property_name.as_str());
+        }
+        // a single `rename_all` is used instead of renaming each field, if all of the property names are camel case
+        let mut rename_all = false;
+        let mut is_camel_case = true;
+        for property_name in properties.keys() {
+            let nm = to_field_name(property_name, &self.config.remap_reserved).map_err(|source| Error::IdentError {
                 source,
                 file: file!(),
                 line: line!(),
             })?;
-            let (mut field_tp_name, field_tp) = self.create_struct_field_type(doc_file, &ns, property_name, property)?;
-            let is_required = required.contains(property_name.as_str());
-            let is_vec = is_vec(&field_tp_name);
+            let nm = nm.to_string();
+            rename_all = rename_all || &nm != property_name;
+            is_camel_case = is_camel_case && &to_serde_camel_case(&nm) == property_name;
+        }
+        let rename_all = rename_all && is_camel_case;
+        for (property_name, composed) in &properties {
+            let ComposedProperty {
+                doc_file,
+                schema: property,
+                is_required,
+                base,
+            } = composed;
+            let doc_file = doc_file.as_path();
+            let nm = to_field_name(property_name, &self.config.remap_reserved).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            let (mut field_tp_name, field_tp) = self.create_struct_field_type(doc_file, &ns, property_name, property, box_properties)?;
+            all_ord = all_ord && (is_local_enum(property) || is_ord_type(&field_tp_name));
+            // a nullable property may be null even if it is required
+            let is_nullable = property.schema.x_nullable == Some(true);
+            let is_required = *is_required && !is_nullable;
+            let is_vec = !is_nullable && is_vec(&field_tp_name);
             if !is_vec {
                 field_tp_name = require(is_required, field_tp_name);
             }
             local_types.extend(field_tp);
+            // see if a field shoud be wrapped in a Box
+            let prop_nm = &PropertyName {
+                file_path: PathBuf::from(doc_file),
+                schema_name: base.as_deref().unwrap_or(struct_name).to_owned(),
+                property_name: property_name.to_string(),
+            };
+            // println!("property {:?}", prop_nm);
+            let is_boxed = self.config.box_properties.contains(prop_nm) || box_properties.contains(prop_nm);
+            if is_boxed {
+                field_tp_name = quote! { Box<#field_tp_name> };
+            }
+            let serde_as = match get_serde_as_type(property) {
+                Some(as_type) if !is_boxed => {
+                    has_serde_as = true;
+                    let as_type = if is_required {
+                        as_type.to_owned()
+                    } else {
+                        format!("Option<{}>", as_type)
+                    };
+                    quote! { #[serde_as(as = #as_type)] }
+                }
+                _ => quote! {},
+            };
             let mut serde_attrs: Vec<TokenStream> = Vec::new();
-            if &nm.to_string() != property_name {
+            if !rename_all && &nm.to_string() != property_name {
                 serde_attrs.push(quote! { rename = #property_name });
             }
+            // a missing optional field is deserialized as its schema default, or as `None` or empty
+            if !is_required {
+                let default_value = if is_local_enum(property) {
+                    create_enum_default_value(&property.schema.common)
+                } else {
+                    create_default_value(&property.schema.common)
+                };
+                match default_value {
+                    Some(value) if !is_vec && !is_boxed => {
+                        let default_fn = to_module_name(&format!("{}_default", property_name)).map_err(|source| Error::IdentError {
+                            source,
+                            file: file!(),
+                            line: line!(),
+                        })?;
+                        let default_path = format!("{}::{}", struct_type_name, default_fn);
+                        serde_attrs.push(quote! { default = #default_path });
+                        default_fns.extend(quote! {
+                            fn #default_fn() -> #field_tp_name {
+                                #value
+                            }
+                        });
+                    }
+                    _ => serde_attrs.push(quote! { default }),
+                }
+            }
             if property.schema.read_only == Some(true) {
                 serde_attrs.push(quote! { skip_serializing });
             } else {
@@ -265,36 +831,113 @@ impl CodeGen {
             } else {
                 quote! {}
             };
-            // see if a field shoud be wrapped in a Box
-            let prop_nm = &PropertyName {
-                file_path: PathBuf::from(doc_file),
-                schema_name: struct_name.to_owned(),
-                property_name: property_name.to_string(),
-            };
-            // println!("property {:?}", prop_nm);
-            if self.config.box_properties.contains(prop_nm) {
-                field_tp_name = quote! { Box<#field_tp_name> };
+            let mut doc = create_doc(property.schema.common.description.as_deref());
+            if let Some(base) = base {
+                let from = format!("From `{}`", base);
+                doc.extend(quote! { #[doc = #from] });
             }
+            if property.schema.x_ms_client_flatten == Some(true) && !is_boxed && !is_vec {
+                flattened.push((nm.clone(), is_required, property));
+            }
+            let deprecated = create_deprecated(property.schema.common.deprecated, property.schema.common.description.as_deref());
             props.extend(quote! {
+                #doc
+                #deprecated
+                #serde_as
                 #serde
                 pub #nm: #field_tp_name,
             });
+            fields.push(StructField {
+                name: nm,
+                tp: field_tp_name,
+                is_required,
+            });
+        }
+
+        let additional_properties_tp = match &schema.schema.additional_properties {
+            Some(AdditionalProperties::Boolean(true)) => Some(quote! { serde_json::Value }),
+            Some(AdditionalProperties::Schema(schema)) => Some(get_type_name_for_schema_ref(schema, AsReference::False)?),
+            _ => None,
+        };
+        if let Some(tp) = additional_properties_tp {
+            all_ord = false;
+            props.extend(quote! {
+                #[serde(flatten)]
+                pub additional_properties: HashMap<String, #tp>,
+            });
+            fields.push(StructField {
+                name: quote! { additional_properties },
+                tp: quote! { HashMap<String, #tp> },
+                is_required: false,
+            });
         }
 
+        // a struct with only optional fields can be created with `default()`
+        let derive_default = if fields.iter().all(|field| !field.is_required) {
+            quote! { Default, }
+        } else {
+            quote! {}
+        };
+        // a struct of comparable fields can be sorted and used as a key in a `BTreeMap` or `HashMap`
+        let derive_ord = if all_ord {
+            quote! { Eq, Hash, PartialOrd, Ord, }
+        } else {
+            quote! {}
+        };
+        let deprecated = create_deprecated(schema.schema.common.deprecated, schema.schema.common.description.as_deref());
+        let doc = create_doc(schema.schema.common.description.as_deref());
+        let serde_rename_all = if rename_all {
+            quote! { #[serde(rename_all = "camelCase")] }
+        } else {
+            quote! {}
+        };
+        let serde_as = if has_serde_as {
+            quote! { #[serde_with::serde_as] }
+        } else {
+            quote! {}
+        };
         let st = quote! {
-            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #doc
+            #serde_as
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, #derive_default #derive_ord)]
+            #serde_rename_all
+            #deprecated
             pub struct #nm {
                 #props
             }
         };
         streams.push(TokenStream::from(st));
+        if !default_fns.is_empty() {
+            streams.push(quote! {
+                impl #nm {
+                    #default_fns
+                }
+            });
+        }
+        streams.push(create_struct_display(&nm, &fields));
+        streams.push(create_struct_json_conversions(&nm));
+        let builder_nm = to_type_name(&format!("{}_builder", struct_name)).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        // a schema with the name of the builder is generated instead of it
+        if !self.schema_type_names.contains(&builder_nm.to_string()) {
+            streams.push(create_struct_builder(&builder_nm, &nm, &fields)?);
+        }
+        if let Some((base_name, base_tp)) = &base_field {
+            streams.push(create_struct_base_conversions(&nm, base_name, base_tp, &fields));
+        }
+        if flattened.len() > 0 {
+            let field_names = fields.iter().map(|field| field.name.to_string()).collect();
+            streams.push(self.create_flattened_getters(&nm, field_names, &flattened, box_properties)?);
+        }
 
         if local_types.len() > 0 {
             let mut types = TokenStream::new();
             local_types.into_iter().for_each(|tp| types.extend(tp));
             streams.push(quote! {
                 pub mod #ns {
-                    use super::*;
                     #types
                 }
             });
@@ -303,6 +946,111 @@ impl CodeGen {
         Ok(streams)
     }
 
+    /// Collects the properties of a base schema and its own bases, keeping the first of any with the same name.
+    fn collect_composed_properties(
+        &self,
+        doc_file: &Path,
+        base: &ReferenceOr<Schema>,
+        properties: &mut IndexMap<String, ComposedProperty>,
+    ) -> Result<()> {
+        let resolved = self
+            .spec
+            .resolve_schema(doc_file, base)
+            .map_err(|source| Error::SpecError { source })?;
+        let base_file = match &resolved.ref_key {
+            Some(ref_key) => ref_key.file_path.clone(),
+            None => doc_file.to_owned(),
+        };
+        let base_name = resolved.ref_key.as_ref().map(|ref_key| ref_key.name.clone());
+        for base in &resolved.schema.all_of {
+            self.collect_composed_properties(&base_file, base, properties)?;
+        }
+        // the discriminator is the enum tag
+        let discriminator = resolved.schema.discriminator.as_deref();
+        let base_properties = self
+            .spec
+            .resolve_schema_map(&base_file, &resolved.schema.properties)
+            .map_err(|source| Error::SpecError { source })?;
+        for (property_name, property) in base_properties {
+            if Some(property_name.as_str()) == discriminator || properties.contains_key(&property_name) {
+                continue;
+            }
+            let is_required = resolved.schema.required.contains(&property_name);
+            properties.insert(
+                property_name,
+                ComposedProperty {
+                    doc_file: base_file.clone(),
+                    schema: property,
+                    is_required,
+                    base: base_name.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Creates getters for the properties of `x-ms-client-flatten` properties,
+    /// so they can be used as if they were fields of the struct while the JSON stays nested.
+    fn create_flattened_getters(
+        &self,
+        nm: &TokenStream,
+        mut names: HashSet<String>,
+        flattened: &[(TokenStream, bool, &ResolvedSchema)],
+        box_properties: &HashSet<PropertyName>,
+    ) -> Result<TokenStream> {
+        let mut getters = TokenStream::new();
+        for (field_name, is_field_required, property) in flattened {
+            let ref_key = match &property.ref_key {
+                Some(ref_key) => ref_key,
+                None => continue,
+            };
+            let ns = to_module_name(&ref_key.name).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            let required: HashSet<&str> = property.schema.required.iter().map(String::as_str).collect();
+            let properties = self
+                .spec
+                .resolve_schema_map(&ref_key.file_path, &property.schema.properties)
+                .map_err(|source| Error::SpecError { source })?;
+            for (property_name, property) in &properties {
+                let getter = to_field_name(property_name, &self.config.remap_reserved).map_err(|source| Error::IdentError {
+                    source,
+                    file: file!(),
+                    line: line!(),
+                })?;
+                let prop_nm = PropertyName {
+                    file_path: ref_key.file_path.clone(),
+                    schema_name: ref_key.name.clone(),
+                    property_name: property_name.to_string(),
+                };
+                if self.config.box_properties.contains(&prop_nm) || box_properties.contains(&prop_nm) || !names.insert(getter.to_string()) {
+                    continue;
+                }
+                let (tp, _) = self.create_struct_field_type(&ref_key.file_path, &ns, property_name, property, box_properties)?;
+                let is_nullable = property.schema.x_nullable == Some(true);
+                let is_optional = !required.contains(property_name.as_str()) && !is_vec(&tp) || is_nullable;
+                let value = match (*is_field_required, is_optional) {
+                    (true, true) => quote! { self.#field_name.#getter.as_ref() },
+                    (true, false) => quote! { Some(&self.#field_name.#getter) },
+                    (false, true) => quote! { self.#field_name.as_ref().and_then(|value| value.#getter.as_ref()) },
+                    (false, false) => quote! { self.#field_name.as_ref().map(|value| &value.#getter) },
+                };
+                getters.extend(quote! {
+                    pub fn #getter(&self) -> Option<&#tp> {
+                        #value
+                    }
+                });
+            }
+        }
+        Ok(quote! {
+            impl #nm {
+                #getters
+            }
+        })
+    }
+
     /// Creates the type reference for a struct field from a struct property.
     /// Optionally, creates a type for a local schema.
     fn create_struct_field_type(
@@ -311,10 +1059,11 @@ impl CodeGen {
         namespace: &TokenStream,
         property_name: &str,
         property: &ResolvedSchema,
+        box_properties: &HashSet<PropertyName>,
     ) -> Result<(TokenStream, Vec<TokenStream>)> {
         match &property.ref_key {
             Some(ref_key) => {
-                let tp = ident(&ref_key.name.to_camel_case()).map_err(|source| Error::IdentError {
+                let tp = to_type_name(&ref_key.name).map_err(|source| Error::IdentError {
                     source,
                     file: file!(),
                     line: line!(),
@@ -323,16 +1072,17 @@ impl CodeGen {
             }
             None => {
                 if is_local_enum(property) {
-                    let (tp_name, tp) = create_enum(namespace, property_name, property)?;
+                    let enum_name = get_enum_name(property).unwrap_or(property_name);
+                    let (tp_name, tp) = create_enum(namespace, enum_name, property)?;
                     Ok((tp_name, vec![tp]))
                 } else if is_local_struct(property) {
-                    let id = ident(&property_name.to_camel_case()).map_err(|source| Error::IdentError {
+                    let id = to_type_name(&property_name).map_err(|source| Error::IdentError {
                         source,
                         file: file!(),
                         line: line!(),
                     })?;
                     let tp_name = quote! {#namespace::#id};
-                    let tps = self.create_struct(doc_file, property_name, property)?;
+                    let tps = self.create_struct(doc_file, property_name, property, box_properties)?;
                     // println!("creating local struct {:?} {}", tp_name, tps.len());
                     Ok((tp_name, tps))
                 } else {
@@ -343,29 +1093,314 @@ impl CodeGen {
     }
 }
 
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error("SpecError")]
-    SpecError { source: spec::Error },
-    #[error("ArrayExpectedToHaveItems")]
-    ArrayExpectedToHaveItems,
-    #[error("NoNameForRef")]
-    NoNameForRef,
-    #[error("IdentError at {}:{} {} ", file, line, source)]
-    IdentError {
-        source: crate::identifier::Error,
-        file: &'static str,
-        line: u32,
-    },
-    #[error("CreateEnumIdentError {} {}", property_name, enum_value)]
-    CreateEnumIdentError {
-        source: identifier::Error,
-        property_name: String,
-        enum_value: String,
-    },
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("SpecError")]
+    SpecError { source: spec::Error },
+    #[error("ArrayExpectedToHaveItems")]
+    ArrayExpectedToHaveItems,
+    #[error("NoNameForRef")]
+    NoNameForRef,
+    #[error("IdentError at {}:{} {} ", file, line, source)]
+    IdentError {
+        source: crate::identifier::Error,
+        file: &'static str,
+        line: u32,
+    },
+    #[error("PathError")]
+    PathError { source: crate::path::Error },
+    #[error("CreateEnumIdentError {} {}", property_name, enum_value)]
+    CreateEnumIdentError {
+        source: identifier::Error,
+        property_name: String,
+        enum_value: String,
+    },
+}
+
+/// Reads an example file, warning and skipping examples that are missing or are not JSON.
+fn read_example(example_path: &Path) -> Option<Value> {
+    let bytes = match std::fs::read(example_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("WARN could not read example {}: {}", example_path.display(), err);
+            return None;
+        }
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(example) => Some(example),
+        Err(err) => {
+            eprintln!("WARN could not parse example {}: {}", example_path.display(), err);
+            None
+        }
+    }
+}
+
+/// The name of the struct with the properties of a discriminated schema.
+fn get_discriminated_base_name(name: &str) -> String {
+    format!("{}Base", name)
+}
+
+/// Creates a `Display` for a struct. A struct with only a required `value` string displays the value, others display as JSON.
+fn create_struct_display(nm: &TokenStream, fields: &[StructField]) -> TokenStream {
+    let is_string_value = match fields {
+        [field] => field.is_required && field.name.to_string() == "value" && field.tp.to_string() == "String",
+        _ => false,
+    };
+    let fmt = if is_string_value {
+        quote! { f.write_str(&self.value) }
+    } else {
+        quote! {
+            let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+            f.write_str(&json)
+        }
+    };
+    quote! {
+        impl std::fmt::Display for #nm {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                #fmt
+            }
+        }
+    }
+}
+
+/// Creates conversions between a struct and a `serde_json::Value`, such as for dynamic JSON from webhooks.
+/// Serializing to a value does not fail, since the maps of a model all have string keys.
+fn create_struct_json_conversions(nm: &TokenStream) -> TokenStream {
+    quote! {
+        impl std::convert::TryFrom<serde_json::Value> for #nm {
+            type Error = serde_json::Error;
+            fn try_from(value: serde_json::Value) -> std::result::Result<Self, Self::Error> {
+                serde_json::from_value(value)
+            }
+        }
+        impl From<#nm> for serde_json::Value {
+            fn from(value: #nm) -> Self {
+                serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+            }
+        }
+    }
+}
+
+/// Creates `AsRef` for the flattened `allOf` base of a struct,
+/// and `From` the base if the rest of the fields are optional.
+fn create_struct_base_conversions(nm: &TokenStream, base_name: &TokenStream, base_tp: &TokenStream, fields: &[StructField]) -> TokenStream {
+    let base_name_str = base_name.to_string();
+    let others: Vec<&StructField> = fields.iter().filter(|field| field.name.to_string() != base_name_str).collect();
+    let from = if others.iter().all(|field| !field.is_required) {
+        let other_names = others.iter().map(|field| &field.name);
+        quote! {
+            impl From<#base_tp> for #nm {
+                fn from(#base_name: #base_tp) -> Self {
+                    Self {
+                        #base_name,
+                        #(#other_names: Default::default(),)*
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        impl AsRef<#base_tp> for #nm {
+            fn as_ref(&self) -> &#base_tp {
+                &self.#base_name
+            }
+        }
+        #from
+    }
+}
+
+/// The names of the items defined in the tokens, not including those in nested modules.
+pub(crate) fn get_item_names(tokens: &TokenStream) -> IndexSet<String> {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    let mut names = IndexSet::new();
+    for pair in trees.windows(2) {
+        if let (TokenTree::Ident(keyword), TokenTree::Ident(name)) = (&pair[0], &pair[1]) {
+            if matches!(keyword.to_string().as_str(), "struct" | "enum" | "type" | "trait" | "mod" | "fn") {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Counts the structs and enums, including those in nested modules.
+pub(crate) fn count_types(tokens: &TokenStream) -> usize {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    let mut count = 0;
+    for (i, tree) in trees.iter().enumerate() {
+        match tree {
+            TokenTree::Ident(keyword) if *keyword == "struct" || *keyword == "enum" => {
+                if matches!(trees.get(i + 1), Some(TokenTree::Ident(_))) {
+                    count += 1;
+                }
+            }
+            TokenTree::Group(group) => count += count_types(&group.stream()),
+            _ => {}
+        }
+    }
+    count
+}
+
+/// Whether the tree at the index is the body of a `mod`.
+fn is_mod_body(trees: &[TokenTree], i: usize) -> bool {
+    match &trees[i] {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace && i >= 2 => {
+            matches!(&trees[i - 2], TokenTree::Ident(keyword) if *keyword == "mod")
+        }
+        _ => false,
+    }
+}
+
+/// Adds the identifiers used in the tokens, not including those in nested modules.
+fn add_used_names(tokens: &TokenStream, names: &mut HashSet<String>) {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    for (i, tree) in trees.iter().enumerate() {
+        match tree {
+            TokenTree::Ident(ident) => {
+                names.insert(ident.to_string());
+            }
+            // the type of a `serde_as` attribute is in a string
+            TokenTree::Group(group) if i > 0 && matches!(&trees[i - 1], TokenTree::Ident(ident) if *ident == "serde_as") => {
+                for tree in group.stream() {
+                    if let TokenTree::Literal(literal) = tree {
+                        let literal = literal.to_string();
+                        let words = literal
+                            .split(|c: char| !c.is_alphanumeric() && c != '_')
+                            .filter(|word| !word.is_empty());
+                        names.extend(words.map(str::to_owned));
+                    }
+                }
+            }
+            TokenTree::Group(group) if !is_mod_body(&trees, i) => add_used_names(&group.stream(), names),
+            _ => {}
+        }
+    }
+}
+
+/// Creates a `use` of the models that are used in a module and are not defined in it.
+fn create_model_imports(tokens: &TokenStream, model_names: &IndexSet<String>, models_path: &TokenStream) -> TokenStream {
+    let mut used = HashSet::new();
+    add_used_names(tokens, &mut used);
+    let defined = get_item_names(tokens);
+    let names: Vec<Ident> = model_names
+        .iter()
+        .filter(|name| used.contains(*name) && !defined.contains(*name))
+        .map(|name| Ident::new(name, Span::call_site()))
+        .collect();
+    if names.is_empty() {
+        quote! {}
+    } else {
+        quote! { use #models_path::{#(#names),*}; }
+    }
+}
+
+/// Adds a `use` of the models to each nested module, with the path to the models for the depth of the module.
+fn add_model_imports(
+    tokens: TokenStream,
+    model_names: &IndexSet<String>,
+    models_path: &dyn Fn(usize) -> TokenStream,
+    depth: usize,
+) -> TokenStream {
+    let trees: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut stream = TokenStream::new();
+    for (i, tree) in trees.iter().enumerate() {
+        match tree {
+            TokenTree::Group(group) if is_mod_body(&trees, i) => {
+                let body = add_model_imports(group.stream(), model_names, models_path, depth + 1);
+                let mut module = create_model_imports(&body, model_names, &models_path(depth + 1));
+                module.extend(body);
+                let mut module = Group::new(Delimiter::Brace, module);
+                module.set_span(group.span());
+                stream.extend(Some(TokenTree::Group(module)));
+            }
+            tree => stream.extend(Some(tree.clone())),
+        }
+    }
+    stream
+}
+
+/// A field of a generated struct, used to create its builder.
+struct StructField {
+    name: TokenStream,
+    tp: TokenStream,
+    is_required: bool,
+}
+
+/// A property of a struct, which may come from one of the bases of a composed `allOf`.
+struct ComposedProperty {
+    doc_file: PathBuf,
+    schema: ResolvedSchema,
+    is_required: bool,
+    /// The name of the base schema it came from
+    base: Option<String>,
+}
+
+/// Creates a builder for a struct, which takes the required fields in `new` and has a setter for each of the other fields.
+/// A setter that would be named `new` or `build` is suffixed with an underscore.
+fn create_struct_builder(builder_nm: &TokenStream, nm: &TokenStream, fields: &[StructField]) -> Result<TokenStream> {
+    let mut builder_props = TokenStream::new();
+    let mut new_params = Vec::new();
+    let mut new_fields = Vec::new();
+    let mut setters = TokenStream::new();
+    let mut build_fields = Vec::new();
+    for StructField { name, tp, is_required } in fields {
+        if *is_required {
+            let field_name = name.to_string();
+            builder_props.extend(quote! { #name: Option<#tp>, });
+            new_params.push(quote! { #name: #tp });
+            new_fields.push(quote! { #name: Some(#name) });
+            build_fields.push(quote! {
+                #name: self.#name.ok_or(BuilderError::MissingField { field: #field_name })?
+            });
+        } else {
+            builder_props.extend(quote! { #name: #tp, });
+            let field_name = name.to_string();
+            let setter_name = if field_name == "new" || field_name == "build" {
+                ident(&format!("{}_", field_name)).map_err(|source| Error::IdentError {
+                    source,
+                    file: file!(),
+                    line: line!(),
+                })?
+            } else {
+                name.clone()
+            };
+            setters.extend(quote! {
+                pub fn #setter_name(mut self, #name: impl Into<#tp>) -> Self {
+                    self.#name = #name.into();
+                    self
+                }
+            });
+            build_fields.push(quote! { #name: self.#name });
+        }
+    }
+    Ok(quote! {
+        #[derive(Clone, Debug, Default)]
+        pub struct #builder_nm {
+            #builder_props
+        }
+        impl #builder_nm {
+            pub fn new(#(#new_params),*) -> Self {
+                Self {
+                    #(#new_fields,)*
+                    ..Default::default()
+                }
+            }
+            #setters
+            pub fn build(self) -> std::result::Result<#nm, BuilderError> {
+                Ok(#nm {
+                    #(#build_fields),*
+                })
+            }
+        }
+    })
 }
 
+/// The header parameter for a request id, which is optional and generated if it is not passed in
+const CLIENT_REQUEST_ID: &str = "x-ms-client-request-id";
+
 /// Whether or not to pass a type is a reference.
 #[derive(Copy, Clone)]
 pub enum AsReference {
@@ -385,6 +1420,18 @@ fn is_string(schema: &SchemaCommon) -> bool {
     matches!(schema.type_, Some(DataType::String))
 }
 
+/// A binary string or a file, sent and received as `bytes::Bytes`
+fn is_binary(schema: &SchemaCommon) -> bool {
+    (is_string(schema) && schema.format.as_deref() == Some("binary")) || matches!(schema.type_, Some(DataType::File))
+}
+
+fn is_binary_ref(schema: &ReferenceOr<Schema>) -> bool {
+    match schema {
+        ReferenceOr::Reference { .. } => false,
+        ReferenceOr::Item(schema) => is_binary(&schema.common),
+    }
+}
+
 fn get_schema_array_items(schema: &SchemaCommon) -> Result<&ReferenceOr<Schema>> {
     Ok(schema.items.as_ref().as_ref().map_or(Err(Error::ArrayExpectedToHaveItems), Ok)?)
 }
@@ -395,8 +1442,48 @@ pub fn create_generated_by_header() -> TokenStream {
     quote! { #![doc = #comment] }
 }
 
+/// Some string formats are serialized with `serde_with::serde_as`, returning the `serde_as` type of the field.
+fn get_serde_as_type(property: &ResolvedSchema) -> Option<&'static str> {
+    let common = &property.schema.common;
+    if property.ref_key.is_some() || !is_string(common) || !common.enum_.is_empty() {
+        return None;
+    }
+    match common.format.as_deref() {
+        Some("date-time") => Some("Rfc3339"),
+        // the `FromStr` of the `Uuid` of the `uuids` feature, or of a `String`
+        Some("uuid") => Some("serde_with::DisplayFromStr"),
+        _ => None,
+    }
+}
+
 fn is_local_enum(property: &ResolvedSchema) -> bool {
-    property.schema.common.enum_.len() > 0
+    property.schema.common.enum_.len() > 0 && !is_model_as_string(property)
+}
+
+/// An `x-ms-enum` with `modelAsString` is a `String`, since values may be added that are not listed.
+fn is_model_as_string(property: &ResolvedSchema) -> bool {
+    match &property.schema.common.x_ms_enum {
+        Some(x_ms_enum) => x_ms_enum.model_as_string == Some(true),
+        None => false,
+    }
+}
+
+/// The enum name from `x-ms-enum`, if there is one.
+fn get_enum_name(property: &ResolvedSchema) -> Option<&str> {
+    property.schema.common.x_ms_enum.as_ref().map(|x_ms_enum| x_ms_enum.name.as_str())
+}
+
+/// The description of an enum value from `x-ms-enum`, if there is one.
+fn get_enum_value_description<'a>(property: &'a ResolvedSchema, value: &str) -> Option<&'a str> {
+    let x_ms_enum = property.schema.common.x_ms_enum.as_ref()?;
+    x_ms_enum
+        .values
+        .iter()
+        .find(|enum_value| match &enum_value.value {
+            Value::String(s) => s == value,
+            other => other.to_string() == value,
+        })
+        .and_then(|enum_value| enum_value.description.as_deref())
 }
 
 fn is_local_struct(property: &ResolvedSchema) -> bool {
@@ -405,44 +1492,308 @@ fn is_local_struct(property: &ResolvedSchema) -> bool {
 
 fn create_enum(namespace: &TokenStream, property_name: &str, property: &ResolvedSchema) -> Result<(TokenStream, TokenStream)> {
     let enum_values = enum_values_as_strings(&property.schema.common.enum_);
-    let id = ident(&property_name.to_camel_case()).map_err(|source| Error::IdentError {
+    // the derived serde impls read and write strings, so other values need their own impls
+    let is_string_enum = enum_values.iter().all(|(_, _, value)| value.is_string());
+    let id = to_type_name(&property_name).map_err(|source| Error::IdentError {
         source,
         file: file!(),
         line: line!(),
     })?;
     let mut values = TokenStream::new();
-    for name in enum_values {
-        let nm = name.to_camel_case_ident().map_err(|source| Error::CreateEnumIdentError {
+    let mut display_arms = TokenStream::new();
+    let mut from_str_arms = TokenStream::new();
+    let mut serde_values = Vec::new();
+    for (variant_name, name, value) in &enum_values {
+        let nm = variant_name.to_camel_case_ident().map_err(|source| Error::CreateEnumIdentError {
             source,
             property_name: property_name.to_owned(),
             enum_value: name.to_owned(),
         })?;
-        let rename = if &nm.to_string() == name {
+        let rename = if !is_string_enum || &nm.to_string() == name {
             quote! {}
         } else {
             quote! { #[serde(rename = #name)] }
         };
-        let value = quote! {
+        let doc = create_doc(get_enum_value_description(property, name));
+        values.extend(quote! {
+            #doc
             #rename
             #nm,
-        };
-        values.extend(value);
+        });
+        display_arms.extend(quote! {
+            Self::#nm => f.write_str(#name),
+        });
+        from_str_arms.extend(quote! {
+            #name => Ok(Self::#nm),
+        });
+        serde_values.push((nm, *value));
     }
-    let nm = ident(&property_name.to_camel_case()).map_err(|source| Error::IdentError {
+    let nm = to_type_name(&property_name).map_err(|source| Error::IdentError {
         source,
         file: file!(),
         line: line!(),
     })?;
+    let (derive_serde, serde_impls) = if is_string_enum {
+        (quote! { , Serialize, Deserialize }, TokenStream::new())
+    } else {
+        (TokenStream::new(), create_enum_serde(&nm, &serde_values))
+    };
+    let doc = create_doc(property.schema.common.description.as_deref());
     let tp = quote! {
-        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        #doc
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord #derive_serde)]
         pub enum #nm {
             #values
         }
+        #serde_impls
+        impl std::fmt::Display for #nm {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match *self {
+                    #display_arms
+                }
+            }
+        }
+        impl std::str::FromStr for #nm {
+            type Err = ParseError;
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                match s {
+                    #from_str_arms
+                    _ => Err(ParseError { value: s.to_owned() }),
+                }
+            }
+        }
     };
     let tp_name = quote! {#namespace::#id};
     Ok((tp_name, tp))
 }
 
+/// Creates the serde impls of an enum, which read and write each variant as its JSON value, such as `1` or `true`.
+fn create_enum_serde(nm: &TokenStream, values: &[(TokenStream, &Value)]) -> TokenStream {
+    let variants: Vec<_> = values.iter().map(|(variant, _)| variant).collect();
+    let json_values: Vec<_> = values.iter().map(|(_, value)| create_json_value(value)).collect();
+    quote! {
+        impl serde::Serialize for #nm {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let value = match self {
+                    #(Self::#variants => serde_json::json!(#json_values),)*
+                };
+                serde::Serialize::serialize(&value, serializer)
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for #nm {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+                #(if value == serde_json::json!(#json_values) {
+                    return Ok(Self::#variants);
+                })*
+                Err(serde::de::Error::custom(format!("unknown enum value {}", value)))
+            }
+        }
+    }
+}
+
+/// The literal of a JSON enum value.
+fn create_json_value(value: &Value) -> TokenStream {
+    match value {
+        Value::String(s) => quote! { #s },
+        Value::Bool(b) => quote! { #b },
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                let lit = Literal::u64_unsuffixed(u);
+                quote! { #lit }
+            } else if let Some(i) = n.as_i64() {
+                let lit = Literal::u64_unsuffixed(i.unsigned_abs());
+                quote! { -#lit }
+            } else {
+                let lit = Literal::f64_unsuffixed(n.as_f64().unwrap_or_default());
+                quote! { #lit }
+            }
+        }
+        _ => quote! { null },
+    }
+}
+
+/// Creates a doc comment from a description, if there is one.
+fn create_doc(description: Option<&str>) -> TokenStream {
+    match description {
+        Some(description) if !description.trim().is_empty() => {
+            let lines = create_doc_lines(description);
+            quote! { #(#[doc = #lines])* }
+        }
+        _ => quote! {},
+    }
+}
+
+/// The lines of a description as a doc comment, which rustdoc must not run as doctests.
+/// A code block is marked as `text`, and the other lines are unindented, so that they are not an indented code block.
+fn create_doc_lines(description: &str) -> Vec<String> {
+    let mut in_code_block = false;
+    description
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            match line.strip_prefix("```") {
+                Some(_) if in_code_block => {
+                    in_code_block = false;
+                    line.to_owned()
+                }
+                Some(_) => {
+                    in_code_block = true;
+                    "```text".to_owned()
+                }
+                None => line.to_owned(),
+            }
+        })
+        .collect()
+}
+
+/// Creates the doc comment of an operation function from its summary, description, parameters, and examples.
+fn create_function_doc(cg: &CodeGen, operation: &Operation, parameters: &[Parameter]) -> Result<TokenStream> {
+    let mut sections = Vec::new();
+    let summary = create_doc(operation.summary.as_deref());
+    if !summary.is_empty() {
+        sections.push(summary);
+    }
+    if operation.description != operation.summary {
+        let doc = create_doc(operation.description.as_deref());
+        if !doc.is_empty() {
+            sections.push(doc);
+        }
+    }
+    if !parameters.is_empty() {
+        let mut doc = quote! { #[doc = "# Parameters"] };
+        for param in parameters {
+            let name = get_param_name(cg, param)?.to_string();
+            let line = match &param.description {
+                Some(description) => format!("- `{}`: {}", name, description.lines().map(str::trim).collect::<Vec<_>>().join(" ")),
+                None => format!("- `{}`", name),
+            };
+            doc.extend(quote! { #[doc = #line] });
+        }
+        sections.push(doc);
+    }
+    if !operation.x_ms_examples.is_empty() {
+        let mut doc = quote! { #[doc = "# Examples"] };
+        for (example_name, example) in &operation.x_ms_examples {
+            let line = match example {
+                ReferenceOr::Reference {
+                    reference: Reference { file: Some(file), .. },
+                    ..
+                } => format!("- {} (`{}`)", example_name, file),
+                _ => format!("- {}", example_name),
+            };
+            doc.extend(quote! { #[doc = #line] });
+        }
+        sections.push(doc);
+    }
+    // the sections are separated by blank lines
+    let mut doc = TokenStream::new();
+    for (i, section) in sections.into_iter().enumerate() {
+        if i > 0 {
+            doc.extend(quote! { #[doc = ""] });
+        }
+        doc.extend(section);
+    }
+    Ok(doc)
+}
+
+/// Creates the `Some` value of the schema default of an optional field, if it is a string, number, or boolean.
+fn create_default_value(common: &SchemaCommon) -> Option<TokenStream> {
+    let format = common.format.as_deref();
+    match (common.type_.as_ref()?, common.default.as_ref()?) {
+        // the string may be parsed as an enum, date-time, or uuid
+        (DataType::String, Value::String(value)) if format != Some("byte") && format != Some("binary") => {
+            Some(quote! { #value.parse().ok() })
+        }
+        (DataType::Integer, Value::Number(value)) => {
+            let value = Literal::i64_unsuffixed(value.as_i64()?);
+            Some(quote! { Some(#value) })
+        }
+        (DataType::Number, Value::Number(value)) => {
+            let value = Literal::f64_unsuffixed(value.as_f64()?);
+            Some(quote! { Some(#value) })
+        }
+        (DataType::Boolean, Value::Bool(value)) => Some(quote! { Some(#value) }),
+        _ => None,
+    }
+}
+
+/// The default of a local enum is parsed from its string form, which is what `FromStr` matches for any enum value
+fn create_enum_default_value(common: &SchemaCommon) -> Option<TokenStream> {
+    let value = match common.default.as_ref()? {
+        Value::String(value) => value.to_owned(),
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        _ => return None,
+    };
+    Some(quote! { #value.parse().ok() })
+}
+
+/// Creates a `#[deprecated]` attribute with the description as the note, if it is deprecated.
+fn create_deprecated(deprecated: Option<bool>, description: Option<&str>) -> TokenStream {
+    if deprecated == Some(true) {
+        let note = description.unwrap_or("Deprecated per API spec");
+        quote! { #[deprecated = #note] }
+    } else {
+        quote! {}
+    }
+}
+
+/// A type made of strings, integers, and bools, possibly in an `Option`, `Vec`, or `Box`, which implements `Ord` and `Hash`.
+fn is_ord_type(tp: &TokenStream) -> bool {
+    fn is_ord(tp: &syn::Type) -> bool {
+        let segment = match tp {
+            syn::Type::Path(path) => match path.path.segments.last() {
+                Some(segment) => segment,
+                None => return false,
+            },
+            _ => return false,
+        };
+        match &segment.arguments {
+            syn::PathArguments::None => matches!(
+                segment.ident.to_string().as_str(),
+                "String" | "bool" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "DateTime" | "Uuid"
+            ),
+            syn::PathArguments::AngleBracketed(args) if ["Option", "Vec", "Box"].contains(&segment.ident.to_string().as_str()) => {
+                args.args.iter().all(|arg| match arg {
+                    syn::GenericArgument::Type(tp) => is_ord(tp),
+                    _ => false,
+                })
+            }
+            _ => false,
+        }
+    }
+    syn::parse2::<syn::Type>(tp.clone()).map_or(false, |tp| is_ord(&tp))
+}
+
+/// The name of a field as renamed by `#[serde(rename_all = "camelCase")]`.
+fn to_serde_camel_case(field: &str) -> String {
+    let field = field.trim_start_matches("r#");
+    let mut pascal = String::new();
+    let mut capitalize = true;
+    for ch in field.chars() {
+        if ch == '_' {
+            capitalize = true;
+        } else if capitalize {
+            pascal.push(ch.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            pascal.push(ch);
+        }
+    }
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}", first.to_ascii_lowercase(), chars.as_str()),
+        None => pascal,
+    }
+}
+
 /// Wraps a type in an Option if is not required.
 fn require(is_required: bool, tp: TokenStream) -> TokenStream {
     if is_required {
@@ -452,11 +1803,22 @@ fn require(is_required: bool, tp: TokenStream) -> TokenStream {
     }
 }
 
-fn enum_values_as_strings(values: &Vec<Value>) -> Vec<&str> {
+/// Gets the variant name, the string form, and the JSON value of each enum value.
+/// Numbers and booleans are named from their string form, such as `_0` for `0`.
+fn enum_values_as_strings(values: &[Value]) -> Vec<(String, String, &Value)> {
     values
         .iter()
         .filter_map(|v| match v {
-            Value::String(s) => Some(s.as_str()),
+            Value::String(s) => Some((s.to_owned(), s.to_owned(), v)),
+            Value::Number(n) => {
+                let value = n.to_string();
+                let variant_name = match value.strip_prefix('-') {
+                    Some(value) => format!("Minus{}", value),
+                    None => value.clone(),
+                };
+                Some((variant_name, value, v))
+            }
+            Value::Bool(b) => Some((b.to_string(), b.to_string(), v)),
             _ => None,
         })
         .collect()
@@ -476,8 +1838,8 @@ fn get_param_type(param: &Parameter) -> Result<TokenStream> {
     Ok(require(is_required || is_array, tp))
 }
 
-fn get_param_name(param: &Parameter) -> Result<TokenStream> {
-    ident(&param.name.to_snake_case()).map_err(|source| Error::IdentError {
+fn get_param_name(cg: &CodeGen, param: &Parameter) -> Result<TokenStream> {
+    to_field_name(&param.name, &cg.config.remap_reserved).map_err(|source| Error::IdentError {
         source,
         file: file!(),
         line: line!(),
@@ -489,18 +1851,52 @@ fn parse_params(param_re: &Regex, path: &str) -> Vec<String> {
     param_re.captures_iter(path).into_iter().map(|c| c[1].to_string()).collect()
 }
 
+/// The format string of a path, with `{}` for each param and the static segments percent-encoded.
 fn format_path(param_re: &Regex, path: &str) -> String {
-    param_re.replace_all(path, "{}").to_string()
+    let mut fpath = String::new();
+    let mut last = 0;
+    for param in param_re.find_iter(path) {
+        fpath.push_str(&encode_path(&path[last..param.start()]));
+        fpath.push_str("{}");
+        last = param.end();
+    }
+    fpath.push_str(&encode_path(&path[last..]));
+    fpath
+}
+
+/// The format string of the URL, which is the base path followed by the path, which starts with a slash.
+fn create_fpath(param_re: &Regex, path: &str) -> String {
+    let path = format_path(param_re, path);
+    if path.starts_with('/') {
+        format!("{{}}{}", path)
+    } else {
+        format!("{{}}/{}", path)
+    }
+}
+
+/// Percent-encodes the characters that are not allowed in a path, which include the braces of the format string.
+fn encode_path(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' | b'?' | b'%' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
 }
 
-fn create_function_params(_cg: &CodeGen, _doc_file: &Path, parameters: &Vec<Parameter>) -> Result<TokenStream> {
+fn create_function_params(cg: &CodeGen, _doc_file: &Path, parameters: &Vec<Parameter>) -> Result<TokenStream> {
     let mut params: Vec<TokenStream> = Vec::new();
     for param in parameters {
-        let name = get_param_name(param)?;
+        let name = get_param_name(cg, param)?;
         let tp = get_param_type(param)?;
         params.push(quote! { #name: #tp });
     }
-    let slf = quote! { operation_config: &crate::OperationConfig };
+    let slf = quote! { config: &crate::OperationConfig };
     params.insert(0, slf);
     Ok(quote! { #(#params),* })
 }
@@ -531,8 +1927,14 @@ fn get_type_name_for_schema(schema: &SchemaCommon, as_ref: AsReference) -> Resul
                     quote! { f64 }
                 }
             }
+            DataType::String if format == Some("binary") => quote! { bytes::Bytes },
             DataType::String => match as_ref {
                 AsReference::True => quote! { &str },
+                // a `chrono::DateTime` when the generated crate has the `dates` feature
+                AsReference::False if format == Some("date-time") => quote! { DateTime },
+                // a `uuid::Uuid` when the generated crate has the `uuids` feature
+                AsReference::False if format == Some("uuid") => quote! { Uuid },
+                AsReference::False if format == Some("byte") => quote! { Bytes },
                 AsReference::False => quote! { String },
             },
             DataType::Boolean => quote! { bool },
@@ -540,7 +1942,7 @@ fn get_type_name_for_schema(schema: &SchemaCommon, as_ref: AsReference) -> Resul
                 AsReference::True => quote! { &serde_json::Value },
                 AsReference::False => quote! { serde_json::Value },
             },
-            DataType::File => todo!("Handle DataType::File"),
+            DataType::File => quote! { bytes::Bytes },
         };
         Ok(ts)
     } else {
@@ -559,7 +1961,7 @@ fn get_type_name_for_schema_ref(schema: &ReferenceOr<Schema>, as_ref: AsReferenc
     match schema {
         ReferenceOr::Reference { reference, .. } => {
             let name = &reference.name.as_ref().map_or(Err(Error::NoNameForRef), Ok)?;
-            let idt = ident(&name.to_camel_case()).map_err(|source| Error::IdentError {
+            let idt = to_type_name(&name).map_err(|source| Error::IdentError {
                 source,
                 file: file!(),
                 line: line!(),
@@ -581,15 +1983,214 @@ fn create_response_type(rsp: &Response) -> Result<Option<TokenStream>> {
     }
 }
 
+/// Creates the statement that gets the response value from the response body.
+/// A binary response body is returned as is instead of being deserialized from JSON.
+/// If the operation produces XML, the Content-Type of the response selects XML or JSON.
+fn create_rsp_value(rsp: &Response, tp: &TokenStream, fname: &TokenStream, produces_xml: bool) -> TokenStream {
+    if rsp.schema.as_ref().map_or(false, is_binary_ref) {
+        quote! {
+            let rsp_value: #tp = rsp_body.clone();
+        }
+    } else if produces_xml {
+        quote! {
+            let is_xml = rsp
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .map_or(false, |content_type| content_type.contains("xml"));
+            let rsp_value: #tp = if is_xml {
+                crate::from_xml(rsp_body).map_err(|source| #fname::Error::DeserializeXmlError { source, body: rsp_body.clone() })?
+            } else {
+                serde_json::from_slice(rsp_body).map_err(|source| #fname::Error::DeserializeError { source, body: rsp_body.clone() })?
+            };
+        }
+    } else {
+        quote! {
+            let rsp_value: #tp = serde_json::from_slice(rsp_body).map_err(|source| #fname::Error::DeserializeError { source, body: rsp_body.clone() })?;
+        }
+    }
+}
+
+/// Creates a `ResponseHeaders` struct with a field for each documented header of the response,
+/// and a `ResponseWithHeaders` that pairs it with the body. Returns `None` if no headers are documented.
+fn create_response_headers(cg: &CodeGen, rsp: &Response) -> Result<Option<TokenStream>> {
+    if rsp.headers.is_empty() {
+        return Ok(None);
+    }
+    let mut fields = TokenStream::new();
+    let mut from_response = TokenStream::new();
+    for header_name in rsp.headers.keys() {
+        let name = to_field_name(header_name, &cg.config.remap_reserved).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        fields.extend(quote! {
+            pub #name: Option<String>,
+        });
+        from_response.extend(quote! {
+            #name: rsp.headers().get(#header_name).and_then(|value| value.to_str().ok()).map(|value| value.to_owned()),
+        });
+    }
+    Ok(Some(quote! {
+        #[derive(Clone, Debug, Default)]
+        pub struct ResponseHeaders {
+            #fields
+        }
+        impl ResponseHeaders {
+            pub fn from_response(rsp: &http::Response<bytes::Bytes>) -> Self {
+                Self {
+                    #from_response
+                }
+            }
+        }
+        #[derive(Debug)]
+        pub struct ResponseWithHeaders<T> {
+            pub body: T,
+            pub headers: ResponseHeaders,
+        }
+    }))
+}
+
+/// An optional query param that is not an array, which can be serialized as part of an options struct
+fn is_option_param(param: &Parameter) -> bool {
+    matches!(param.in_, ParameterType::Query) && !param.required.unwrap_or(false) && !is_array(&param.common)
+}
+
+/// Creates an `Options` struct for the optional query params of an operation,
+/// along with the code that serializes it to the query string.
+fn ts_options_struct_and_query(cg: &CodeGen, fname: &TokenStream, option_params: &[Parameter]) -> Result<(TokenStream, TokenStream)> {
+    let mut fields = TokenStream::new();
+    let mut setters = TokenStream::new();
+    for param in option_params {
+        let param_name = &param.name;
+        let name = get_param_name(cg, param)?;
+        let tp = get_type_name_for_schema(&param.common, AsReference::False)?;
+        fields.extend(quote! {
+            #[serde(rename = #param_name, skip_serializing_if = "Option::is_none")]
+            pub #name: Option<#tp>,
+        });
+        setters.extend(quote! {
+            pub fn #name(mut self, #name: impl Into<#tp>) -> Self {
+                self.#name = Some(#name.into());
+                self
+            }
+        });
+    }
+    let ts_options_struct = quote! {
+        #[derive(Clone, Debug, Default, serde::Serialize)]
+        pub struct Options {
+            #fields
+        }
+        impl Options {
+            pub fn new() -> Self {
+                Self::default()
+            }
+            #setters
+        }
+    };
+    let ts_options_query = quote! {
+        if let Some(options) = options {
+            let options_query = serde_urlencoded::to_string(options).map_err(|source| #fname::Error::SerializeError { source: Box::new(source) })?;
+            if !options_query.is_empty() {
+                let query = match url.query() {
+                    Some(query) if !query.is_empty() => format!("{}&{}", query, options_query),
+                    _ => options_query,
+                };
+                url.set_query(Some(&query));
+            }
+        }
+    };
+    Ok((ts_options_struct, ts_options_query))
+}
+
+/// The boundary between the parts of a multipart/form-data body
+const FORM_BOUNDARY: &str = "AutoRustFormBoundary7MA4YWxkTrZu0gW";
+
+/// Creates the request body from the formData params.
+/// It is multipart/form-data if the operation consumes it or there is a file, otherwise it is URL encoded.
+fn create_form_body(cg: &CodeGen, operation_verb: &OperationVerb, form_params: &[&Parameter]) -> Result<TokenStream> {
+    let has_file = form_params.iter().any(|param| matches!(param.common.type_, Some(DataType::File)));
+    let is_multipart = has_file
+        || operation_verb
+            .operation()
+            .consumes
+            .iter()
+            .any(|consumes| consumes == "multipart/form-data");
+    let mut ts = TokenStream::new();
+    if is_multipart {
+        ts.extend(quote! {
+            let mut form_body: Vec<u8> = Vec::new();
+        });
+    } else {
+        ts.extend(quote! {
+            let mut form = url::form_urlencoded::Serializer::new(String::new());
+        });
+    }
+    for param in form_params {
+        let param_name = &param.name;
+        let param_name_var = get_param_name(cg, param)?;
+        let is_file = matches!(param.common.type_, Some(DataType::File));
+        let value = if is_array(&param.common) {
+            quote! { #param_name_var.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",") }
+        } else if is_string(&param.common) {
+            quote! { #param_name_var.to_owned() }
+        } else {
+            quote! { #param_name_var.to_string() }
+        };
+        let part = if is_multipart {
+            let (disposition, value) = if is_file {
+                (
+                    format!("form-data; name=\"{}\"; filename=\"{}\"", param_name, param_name),
+                    quote! { #param_name_var.as_ref() },
+                )
+            } else {
+                (format!("form-data; name=\"{}\"", param_name), quote! { #value.as_bytes() })
+            };
+            quote! {
+                form_body.extend_from_slice(format!("--{}\r\nContent-Disposition: {}\r\n\r\n", #FORM_BOUNDARY, #disposition).as_bytes());
+                form_body.extend_from_slice(#value);
+                form_body.extend_from_slice(b"\r\n");
+            }
+        } else {
+            quote! {
+                form.append_pair(#param_name, &#value);
+            }
+        };
+        if param.required.unwrap_or(false) || is_array(&param.common) {
+            ts.extend(part);
+        } else {
+            ts.extend(quote! {
+                if let Some(#param_name_var) = #param_name_var {
+                    #part
+                }
+            });
+        }
+    }
+    if is_multipart {
+        let content_type = format!("multipart/form-data; boundary={}", FORM_BOUNDARY);
+        ts.extend(quote! {
+            form_body.extend_from_slice(format!("--{}--\r\n", #FORM_BOUNDARY).as_bytes());
+            req_builder = req_builder.header(http::header::CONTENT_TYPE, #content_type);
+            let req_body = bytes::Bytes::from(form_body);
+        });
+    } else {
+        ts.extend(quote! {
+            req_builder = req_builder.header(http::header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+            let req_body = bytes::Bytes::from(form.finish());
+        });
+    }
+    Ok(ts)
+}
+
 fn create_function(
     cg: &CodeGen,
     doc_file: &Path,
     path: &str,
-    _item: &PathItem,
     operation_verb: &OperationVerb,
     param_re: &Regex,
     function_name: &str,
-) -> Result<TokenStream> {
+) -> Result<(TokenStream, TokenStream)> {
     let fname = ident(function_name).map_err(|source| Error::IdentError {
         source,
         file: file!(),
@@ -601,7 +2202,7 @@ fn create_function(
     let params: Result<Vec<_>> = params
         .iter()
         .map(|s| {
-            Ok(ident(&s.to_snake_case()).map_err(|source| Error::IdentError {
+            Ok(to_field_name(s, &cg.config.remap_reserved).map_err(|source| Error::IdentError {
                 source,
                 file: file!(),
                 line: line!(),
@@ -611,7 +2212,7 @@ fn create_function(
     let params = params?;
     let url_str_args = quote! { #(#params),* };
 
-    let fpath = format!("{{}}{}", &format_path(param_re, path));
+    let fpath = create_fpath(param_re, path);
 
     let parameters: Vec<Parameter> = cg
         .spec
@@ -623,9 +2224,30 @@ fn create_function(
     if cg.api_version().is_some() {
         skip.insert("api-version");
     }
-    let parameters = parameters.into_iter().filter(|p| !skip.contains(p.name.as_str())).collect();
+    // the client request id may be passed, or else it is generated
+    let parameters: Vec<Parameter> = parameters
+        .into_iter()
+        .filter(|p| !skip.contains(p.name.as_str()))
+        .map(|mut p| {
+            if p.name == CLIENT_REQUEST_ID {
+                p.required = Some(false);
+            }
+            p
+        })
+        .collect();
 
-    let fparams = create_function_params(cg, doc_file, &parameters)?;
+    // optional query params may be grouped into an options struct
+    let (option_params, parameters): (Vec<Parameter>, Vec<Parameter>) = parameters
+        .into_iter()
+        .partition(|p| cg.config.group_query_options && is_option_param(p));
+
+    let mut fparams = create_function_params(cg, doc_file, &parameters)?;
+    let options = if option_params.len() > 0 {
+        fparams.extend(quote! { , options: Option<&#fname::Options> });
+        Some(ts_options_struct_and_query(cg, &fname, &option_params)?)
+    } else {
+        None
+    };
 
     // see if there is a body parameter
     // let fresponse = create_function_return(operation_verb)?;
@@ -648,29 +2270,19 @@ fn create_function(
     ts_request_builder.extend(req_verb);
 
     // auth
-    ts_request_builder.extend(quote! {
-        if let Some(token_credential) = operation_config.token_credential() {
-            let token_response = token_credential
-                .get_token(operation_config.token_credential_resource()).await
-                .map_err(|source| #fname::Error::GetTokenError{source})?;
-            req_builder = req_builder.header(http::header::AUTHORIZATION, format!("Bearer {}", token_response.token.secret()));
-        }
-    });
+    let api_key = get_api_key(cg, doc_file);
+    let ts_auth = create_auth(api_key.as_ref(), quote! { #fname::Error });
+    ts_request_builder.extend(ts_auth.clone());
 
-    // api-version param
-    if has_param_api_version {
-        if let Some(_api_version) = cg.api_version() {
-            ts_request_builder.extend(quote! {
-                url.query_pairs_mut().append_pair("api-version", operation_config.api_version());
-            });
-        }
-    }
+    // api-version param, which is added whether or not there is a token
+    ts_request_builder.extend(create_api_version_query(has_param_api_version, cg.api_version()));
 
     // params
     let mut has_body_parameter = false;
+    let mut form_params = Vec::new();
     for param in &parameters {
         let param_name = &param.name;
-        let param_name_var = get_param_name(&param)?;
+        let param_name_var = get_param_name(cg, &param)?;
         let required = param.required.unwrap_or(false);
         match param.in_ {
             ParameterType::Path => {} // handled above
@@ -679,25 +2291,44 @@ fn create_function(
                 let query_body = if is_array {
                     let collection_format = param.collection_format.as_ref().unwrap_or(&CollectionFormat::Csv);
                     match collection_format {
-                        CollectionFormat::Multi => Some(
-                            if is_string(&param.common){
+                        CollectionFormat::Multi => Some(if is_string(&param.common) {
+                            quote! {
+                                for value in #param_name_var {
+                                    url.query_pairs_mut().append_pair(#param_name, value);
+                                }
+                            }
+                        } else {
+                            quote! {
+                                for value in #param_name_var {
+                                    url.query_pairs_mut().append_pair(#param_name, value.to_string().as_str());
+                                }
+                            }
+                        }),
+                        CollectionFormat::Csv | CollectionFormat::Ssv | CollectionFormat::Tsv | CollectionFormat::Pipes => {
+                            let delimiter = match collection_format {
+                                CollectionFormat::Ssv => " ",
+                                CollectionFormat::Tsv => "\t",
+                                CollectionFormat::Pipes => "|",
+                                _ => ",",
+                            };
+                            let values = if is_string(&param.common) {
+                                quote! { #param_name_var.join(#delimiter) }
+                            } else {
+                                quote! { #param_name_var.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(#delimiter) }
+                            };
+                            // an optional array that is empty is left out
+                            Some(if required {
                                 quote! {
-                                    for value in #param_name_var {
-                                        url.query_pairs_mut().append_pair(#param_name, value);
-                                    }
+                                    url.query_pairs_mut().append_pair(#param_name, &#values);
                                 }
                             } else {
                                 quote! {
-                                    for value in #param_name_var {
-                                        url.query_pairs_mut().append_pair(#param_name, value.to_string().as_str());
+                                    if !#param_name_var.is_empty() {
+                                        url.query_pairs_mut().append_pair(#param_name, &#values);
                                     }
                                 }
-                            }
-                        ),
-                        CollectionFormat::Csv | // TODO #71
-                        CollectionFormat::Ssv |
-                        CollectionFormat::Tsv |
-                        CollectionFormat::Pipes => None,
+                            })
+                        }
                     }
                 } else {
                     Some(if is_string(&param.common) {
@@ -723,51 +2354,86 @@ fn create_function(
                 }
             }
             ParameterType::Header => {
-                if required {
-                    ts_request_builder.extend(quote! {
-                        req_builder = req_builder.header(#param_name, #param_name_var);
-                    });
-                } else {
-                    ts_request_builder.extend(quote! {
-                        if let Some(#param_name_var) = #param_name_var {
-                            req_builder = req_builder.header(#param_name, #param_name_var);
-                        }
-                    });
-                }
-            }
-            ParameterType::Body => {
-                has_body_parameter = true;
-                if required {
-                    ts_request_builder.extend(quote! {
-                        let req_body = azure_core::to_json(#param_name_var).map_err(|source| #fname::Error::SerializeError{source})?;
-                    });
+                let header_value = if is_string(&param.common) {
+                    quote! { #param_name_var }
                 } else {
+                    quote! { #param_name_var.to_string() }
+                };
+                if param_name == CLIENT_REQUEST_ID {
+                    // one is generated if it is not passed, when the generated crate has the `uuids` feature
                     ts_request_builder.extend(quote! {
-                        let req_body =
-                            if let Some(#param_name_var) = #param_name_var {
-                                azure_core::to_json(#param_name_var).map_err(|source| #fname::Error::SerializeError{source})?
-                            } else {
-                                bytes::Bytes::from_static(azure_core::EMPTY_BODY)
-                            };
+                        match #param_name_var {
+                            Some(#param_name_var) => {
+                                req_builder = req_builder.header(#param_name, #header_value);
+                            }
+                            None => {
+                                #[cfg(feature = "uuids")]
+                                {
+                                    req_builder = req_builder.header(#param_name, uuid::Uuid::new_v4().to_string());
+                                }
+                            }
+                        }
                     });
-                }
-            }
-            ParameterType::Form => {
-                if required {
+                } else if required {
                     ts_request_builder.extend(quote! {
-                        req_builder = req_builder.form(#param_name_var);
+                        req_builder = req_builder.header(#param_name, #header_value);
                     });
                 } else {
                     ts_request_builder.extend(quote! {
                         if let Some(#param_name_var) = #param_name_var {
-                            req_builder = req_builder.form(#param_name_var);
+                            req_builder = req_builder.header(#param_name, #header_value);
                         }
                     });
                 }
             }
+            ParameterType::Body => {} // handled below
+            ParameterType::Form => form_params.push(param),
+        }
+    }
+
+    // the body param is serialized to JSON, unless it is binary
+    if let Some((param, param_name_var)) = create_function_body_param(cg, &parameters)? {
+        has_body_parameter = true;
+        let required = param.required.unwrap_or(false);
+        let is_binary = param.schema.as_ref().map_or(false, is_binary_ref) || is_binary(&param.common);
+        if is_binary {
+            ts_request_builder.extend(quote! {
+                req_builder = req_builder.header(http::header::CONTENT_TYPE, "application/octet-stream");
+            });
+            if required {
+                ts_request_builder.extend(quote! {
+                    let req_body = #param_name_var;
+                });
+            } else {
+                ts_request_builder.extend(quote! {
+                    let req_body = #param_name_var.unwrap_or_else(|| bytes::Bytes::from_static(azure_core::EMPTY_BODY));
+                });
+            }
+        } else {
+            if matches!(
+                operation_verb,
+                OperationVerb::Post(_) | OperationVerb::Put(_) | OperationVerb::Patch(_)
+            ) {
+                let content_type = get_body_content_type(operation_verb.operation());
+                ts_request_builder.extend(quote! {
+                    req_builder = req_builder.header(http::header::CONTENT_TYPE, #content_type);
+                });
+            }
+            ts_request_builder.extend(create_json_body(required, &param_name_var, &fname));
         }
     }
 
+    // the options struct is serialized to the query string
+    if let Some((_, ts_options_query)) = &options {
+        ts_request_builder.extend(ts_options_query.clone());
+    }
+
+    // formData params are all in the body
+    if form_params.len() > 0 {
+        has_body_parameter = true;
+        ts_request_builder.extend(create_form_body(cg, operation_verb, &form_params)?);
+    }
+
     if !has_body_parameter {
         ts_request_builder.extend(quote! {
             let req_body = bytes::Bytes::from_static(azure_core::EMPTY_BODY);
@@ -787,9 +2453,33 @@ fn create_function(
     let is_single_response = success_responses.len() == 1;
     let has_default_response = has_default_response(responses);
 
+    let pageable = if is_single_response {
+        get_pageable_next_link(cg, doc_file, operation_verb, &success_responses[0])?
+    } else {
+        None
+    };
+    // without x-ms-pageable, a list of values with a nextLink may also be streamed
+    let list_next_link = if pageable.is_none() && is_single_response && cg.config.generate_streams {
+        get_list_next_link(cg, doc_file, &success_responses[0])?
+    } else {
+        None
+    };
+
+    // the documented headers of a single response are returned along with the body,
+    // except for streams, which follow the next link in the body
+    let response_headers = if is_single_response && pageable.is_none() && list_next_link.is_none() {
+        create_response_headers(cg, &success_responses[0])?
+    } else {
+        None
+    };
+
     let fresponse = if is_single_response {
         let tp = create_response_type(&success_responses[0])?.unwrap_or(quote! { () });
-        quote! { std::result::Result<#tp, #fname::Error> }
+        if response_headers.is_some() {
+            quote! { std::result::Result<#fname::ResponseWithHeaders<#tp>, #fname::Error> }
+        } else {
+            quote! { std::result::Result<#tp, #fname::Error> }
+        }
     } else {
         quote! { std::result::Result<#fname::Response, #fname::Error> }
     };
@@ -850,6 +2540,11 @@ fn create_function(
         });
     }
 
+    let produces_xml = operation_verb
+        .operation()
+        .produces
+        .iter()
+        .any(|produces| produces == "application/xml" || produces == "text/xml");
     let mut match_status = TokenStream::new();
     for (status_code, rsp) in &success_responses {
         match status_code {
@@ -866,20 +2561,32 @@ fn create_function(
                     line: line!(),
                 })?;
                 if is_single_response {
+                    let ok_value = |rsp_value: TokenStream| match &response_headers {
+                        Some(_) => quote! {
+                            #fname::ResponseWithHeaders {
+                                body: #rsp_value,
+                                headers: #fname::ResponseHeaders::from_response(&rsp),
+                            }
+                        },
+                        None => rsp_value,
+                    };
                     match tp {
                         Some(tp) => {
+                            let rsp_value = create_rsp_value(rsp, &tp, &fname, produces_xml);
+                            let ok_value = ok_value(quote! { rsp_value });
                             match_status.extend(quote! {
                                 http::StatusCode::#status_code_name => {
                                     let rsp_body = rsp.body();
-                                    let rsp_value: #tp = serde_json::from_slice(rsp_body).map_err(|source| #fname::Error::DeserializeError { source, body: rsp_body.clone() })?;
-                                    Ok(rsp_value)
+                                    #rsp_value
+                                    Ok(#ok_value)
                                 }
                             });
                         }
                         None => {
+                            let ok_value = ok_value(quote! { () });
                             match_status.extend(quote! {
                                 http::StatusCode::#status_code_name => {
-                                    Ok(())
+                                    Ok(#ok_value)
                                 }
                             });
                         }
@@ -887,10 +2594,11 @@ fn create_function(
                 } else {
                     match tp {
                         Some(tp) => {
+                            let rsp_value = create_rsp_value(rsp, &tp, &fname, produces_xml);
                             match_status.extend(quote! {
                                 http::StatusCode::#status_code_name => {
                                     let rsp_body = rsp.body();
-                                    let rsp_value: #tp = serde_json::from_slice(rsp_body).map_err(|source| #fname::Error::DeserializeError { source, body: rsp_body.clone() })?;
+                                    #rsp_value
                                     Ok(#fname::Response::#response_type_name(rsp_value))
                                 }
                             });
@@ -981,24 +2689,200 @@ fn create_function(
         });
     }
 
-    let func = quote! {
-        pub async fn #fname(#fparams) -> #fresponse {
-            let http_client = operation_config.http_client();
-            let url_str = &format!(#fpath, operation_config.base_path(), #url_str_args);
-            let mut url = url::Url::parse(url_str).map_err(|source| #fname::Error::ParseUrlError{source})?;
-            let mut req_builder = http::request::Builder::new();
-            #ts_request_builder
-            req_builder = req_builder.uri(url.as_str());
-            let req = req_builder.body(req_body).map_err(|source| #fname::Error::BuildRequestError{source})?;
-            let rsp = http_client.execute_request(req).await.map_err(|source| #fname::Error::ExecuteRequestError{source})?;
-            match rsp.status() {
-                #match_status
+    // long-running operations poll until done before matching the final response
+    let is_long_running = operation_verb.operation().x_ms_long_running_operation == Some(true);
+    let (poll_until_done, lro_poller) = if is_long_running {
+        (
+            quote! { let rsp = #fname::poll_until_done(config, &url, rsp).await?; },
+            create_lro_poller(operation_verb, create_auth(api_key.as_ref(), quote! { Error })),
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let lro_errors = if is_long_running {
+        quote! {
+            #[error("Long-running operation ended with status {}", status)]
+            LongRunningOperationError { status: String, body: bytes::Bytes },
+        }
+    } else {
+        quote! {}
+    };
+
+    let fbody = quote! {
+        let url_str = &format!(#fpath, config.base_path(), #url_str_args);
+        let mut url = url::Url::parse(url_str).map_err(|source| #fname::Error::ParseUrlError{source})?;
+        let mut req_builder = http::request::Builder::new();
+        #ts_request_builder
+        req_builder = req_builder.uri(url.as_str());
+        let req = req_builder.body(req_body).map_err(|source| #fname::Error::BuildRequestError{source})?;
+        let rsp = crate::execute_request(config, req).await.map_err(|source| #fname::Error::ExecuteRequestError{source})?;
+        #poll_until_done
+        match rsp.status() {
+            #match_status
+        }
+    };
+
+    // creates a stream that gets the first page with `first_page` and follows the next links
+    let create_stream =
+        |stream_name: &TokenStream, first_page: &TokenStream, next_link: TokenStream, required: bool| -> Result<TokenStream> {
+            let tp = create_response_type(&success_responses[0])?.unwrap_or(quote! { () });
+            let fname_next_page = ident(&format!("{}_next_page", function_name)).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            let fparams_lifetime = with_lifetime(fparams.clone());
+            let mut fargs = vec![quote! { config }];
+            for param in &parameters {
+                fargs.push(get_param_name(cg, param)?);
+            }
+            if options.is_some() {
+                fargs.push(quote! { options });
+            }
+            let next_link_value = if required {
+                quote! { Some(rsp_value.#next_link.clone()) }
+            } else {
+                quote! { rsp_value.#next_link.clone() }
+            };
+            Ok(quote! {
+                pub fn #stream_name<'a>(#fparams_lifetime) -> impl futures::Stream<Item = std::result::Result<#tp, #fname::Error>> + 'a {
+                    futures::stream::unfold(Some(None), move |next_link: Option<Option<String>>| async move {
+                        let rsp_value = match next_link? {
+                            None => #first_page(#(#fargs),*).await,
+                            Some(next_link) => #fname_next_page(config, &next_link).await,
+                        };
+                        match rsp_value {
+                            Ok(rsp_value) => {
+                                let next_link = #next_link_value.filter(|next_link: &String| !next_link.is_empty());
+                                Some((Ok(rsp_value), next_link.map(Some)))
+                            }
+                            Err(err) => Some((Err(err), None)),
+                        }
+                    })
+                }
+                async fn #fname_next_page(config: &crate::OperationConfig, next_link: &str) -> #fresponse {
+                    let mut url = url::Url::parse(next_link).map_err(|source| #fname::Error::ParseUrlError{source})?;
+                    let mut req_builder = http::request::Builder::new();
+                    req_builder = req_builder.method(http::Method::GET);
+                    #ts_auth
+                    req_builder = req_builder.uri(url.as_str());
+                    let req_body = bytes::Bytes::from_static(azure_core::EMPTY_BODY);
+                    let req = req_builder.body(req_body).map_err(|source| #fname::Error::BuildRequestError{source})?;
+                    let rsp = crate::execute_request(config, req).await.map_err(|source| #fname::Error::ExecuteRequestError{source})?;
+                    match rsp.status() {
+                        #match_status
+                    }
+                }
+            })
+        };
+
+    let operation = operation_verb.operation();
+    let deprecated = create_deprecated(operation.deprecated, operation.description.as_deref());
+    let fdoc = create_function_doc(cg, operation, &parameters)?;
+
+    // the methods of the `Client` call the functions with its config
+    let mut method_params = Vec::new();
+    let mut method_args = vec![quote! { &self.config }];
+    for param in &parameters {
+        let name = get_param_name(cg, param)?;
+        let tp = get_param_type(param)?;
+        method_params.push(quote! { #name: #tp });
+        method_args.push(name);
+    }
+    if options.is_some() {
+        method_params.push(quote! { options: Option<&#fname::Options> });
+        method_args.push(quote! { options });
+    }
+    let create_method = |method_suffix: &str, function: &TokenStream, is_stream: bool| -> Result<TokenStream> {
+        let method_name = ident(&format!("{}{}", function_name, method_suffix)).map_err(|source| Error::IdentError {
+            source,
+            file: file!(),
+            line: line!(),
+        })?;
+        if is_stream {
+            let tp = create_response_type(&success_responses[0])?.unwrap_or(quote! { () });
+            let method_params = with_lifetime(quote! { #(#method_params),* });
+            Ok(quote! {
+                #fdoc
+                #deprecated
+                pub fn #method_name<'a>(&'a self, #method_params) -> impl futures::Stream<Item = std::result::Result<#tp, #fname::Error>> + 'a {
+                    #function(#(#method_args),*)
+                }
+            })
+        } else {
+            Ok(quote! {
+                #fdoc
+                #deprecated
+                pub async fn #method_name(&self, #(#method_params),*) -> #fresponse {
+                    #function(#(#method_args),*).await
+                }
+            })
+        }
+    };
+
+    let mut methods = TokenStream::new();
+    let func = match (pageable, list_next_link) {
+        (Some((next_link, next_link_required)), _) => {
+            // the stream takes the name of the operation
+            let fname_page = ident(&format!("{}_page", function_name)).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            let stream = create_stream(&fname, &fname_page, next_link, next_link_required)?;
+            methods.extend(create_method("", &fname, true)?);
+            quote! {
+                #fdoc
+                #deprecated
+                #stream
+                async fn #fname_page(#fparams) -> #fresponse {
+                    #fbody
+                }
+            }
+        }
+        (None, Some((next_link, next_link_required))) => {
+            // the stream is in addition to the operation
+            let fname_all = ident(&format!("{}_all", function_name)).map_err(|source| Error::IdentError {
+                source,
+                file: file!(),
+                line: line!(),
+            })?;
+            let stream = create_stream(&fname_all, &fname, next_link, next_link_required)?;
+            methods.extend(create_method("", &fname, false)?);
+            methods.extend(create_method("_all", &fname_all, true)?);
+            quote! {
+                #fdoc
+                #deprecated
+                pub async fn #fname(#fparams) -> #fresponse {
+                    #fbody
+                }
+                #fdoc
+                #deprecated
+                #stream
+            }
+        }
+        (None, None) => {
+            methods.extend(create_method("", &fname, false)?);
+            quote! {
+                #fdoc
+                #deprecated
+                pub async fn #fname(#fparams) -> #fresponse {
+                    #fbody
+                }
             }
         }
+    };
+
+    let options_struct = options.map(|(ts_options_struct, _)| ts_options_struct);
+    let func = quote! {
+        #func
         pub mod #fname {
-            use crate::{models, models::*};
+            use crate::models;
 
             #response_enum
+            #response_headers
+            #options_struct
+            #lro_poller
 
             #[derive(Debug, thiserror::Error)]
             pub enum Error {
@@ -1013,18 +2897,521 @@ fn create_function(
                 SerializeError { source: Box<dyn std::error::Error + Sync + Send> },
                 #[error("Failed to deserialize response body: {}", source)]
                 DeserializeError { source: serde_json::Error, body: bytes::Bytes },
+                #[error("Failed to deserialize XML response body: {}", source)]
+                DeserializeXmlError { source: Box<dyn std::error::Error + Sync + Send>, body: bytes::Bytes },
                 #[error("Failed to get access token: {}", source)]
                 GetTokenError { source: azure_core::errors::AzureError },
+                #lro_errors
+            }
+        }
+    };
+    Ok((TokenStream::from(func), methods))
+}
+
+/// Serializes the body param to JSON, or sends an empty body if the optional param is `None`.
+fn create_json_body(required: bool, param_name_var: &TokenStream, fname: &TokenStream) -> TokenStream {
+    if required {
+        quote! {
+            let req_body = azure_core::to_json(#param_name_var).map_err(|source| #fname::Error::SerializeError{source})?;
+        }
+    } else {
+        quote! {
+            let req_body =
+                if let Some(#param_name_var) = #param_name_var {
+                    azure_core::to_json(#param_name_var).map_err(|source| #fname::Error::SerializeError{source})?
+                } else {
+                    bytes::Bytes::from_static(azure_core::EMPTY_BODY)
+                };
+        }
+    }
+}
+
+/// The Content-Type of the body, which is XML only if the operation consumes XML and not JSON.
+fn get_body_content_type(operation: &Operation) -> &str {
+    let consumes_json = operation.consumes.iter().any(|consumes| consumes.contains("json"));
+    match operation
+        .consumes
+        .iter()
+        .find(|consumes| consumes.as_str() == "application/xml" || consumes.as_str() == "text/xml")
+    {
+        Some(xml) if !consumes_json => xml,
+        _ => "application/json",
+    }
+}
+
+/// Finds the body param and its name, since there is at most one.
+fn create_function_body_param<'a>(cg: &CodeGen, parameters: &'a [Parameter]) -> Result<Option<(&'a Parameter, TokenStream)>> {
+    match parameters.iter().find(|param| matches!(param.in_, ParameterType::Body)) {
+        Some(param) => Ok(Some((param, get_param_name(cg, param)?))),
+        None => Ok(None),
+    }
+}
+
+/// Adds the `api-version` query param from the config, if the operation has the param and the crate has an api version.
+fn create_api_version_query(has_param_api_version: bool, api_version: Option<&str>) -> TokenStream {
+    if has_param_api_version && api_version.is_some() {
+        quote! {
+            url.query_pairs_mut().append_pair("api-version", config.api_version());
+        }
+    } else {
+        quote! {}
+    }
+}
+
+/// An API key from the `securityDefinitions`, with its name and whether it is in the header or query
+struct ApiKey {
+    name: String,
+    location: String,
+}
+
+/// Gets the first API key in the `securityDefinitions` of the doc
+fn get_api_key(cg: &CodeGen, doc_file: &Path) -> Option<ApiKey> {
+    let doc = cg.spec.docs().get(doc_file)?;
+    let security_definitions = serde_json::to_value(&doc.security_definitions).ok()?;
+    security_definitions.as_object()?.values().find_map(|security| {
+        if security.get("type")?.as_str()? != "apiKey" {
+            return None;
+        }
+        Some(ApiKey {
+            name: security.get("name")?.as_str()?.to_owned(),
+            location: security.get("in")?.as_str()?.to_owned(),
+        })
+    })
+}
+
+/// Creates the code that authenticates a request with the `Credentials` of the config.
+/// An API key is only sent if the spec has one in its `securityDefinitions`.
+fn create_auth(api_key: Option<&ApiKey>, error: TokenStream) -> TokenStream {
+    let mut ts = quote! {
+        if let Some(token_credential) = config.token_credential() {
+            let token_response = token_credential
+                .get_token(config.token_credential_resource()).await
+                .map_err(|source| #error::GetTokenError{source})?;
+            req_builder = req_builder.header(http::header::AUTHORIZATION, format!("Bearer {}", token_response.token.secret()));
+        }
+        if let Some(token) = config.bearer_token() {
+            req_builder = req_builder.header(http::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+    };
+    if let Some(ApiKey { name, location }) = api_key {
+        if location == "query" {
+            ts.extend(quote! {
+                if let Some(api_key) = config.api_key() {
+                    url.query_pairs_mut().append_pair(#name, api_key);
+                }
+            });
+        } else {
+            ts.extend(quote! {
+                if let Some(api_key) = config.api_key() {
+                    req_builder = req_builder.header(#name, api_key);
+                }
+            });
+        }
+    }
+    ts
+}
+
+/// Creates a `Poller` and `poll_until_done` for an `x-ms-long-running-operation`,
+/// following the Azure-AsyncOperation or Location header of the initial response.
+fn create_lro_poller(operation_verb: &OperationVerb, ts_auth: TokenStream) -> TokenStream {
+    // a PUT or PATCH gets the resource once the async operation succeeds
+    let succeeded = if matches!(operation_verb, OperationVerb::Put(_) | OperationVerb::Patch(_)) {
+        quote! { return get(config, &poller.resource_url).await; }
+    } else {
+        quote! {
+            return match &poller.location_url {
+                Some(location_url) => get(config, location_url).await,
+                None => Ok(rsp),
+            };
+        }
+    };
+    quote! {
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum PollKind {
+            AsyncOperation,
+            Location,
+        }
+
+        #[derive(Clone, Debug)]
+        pub struct Poller {
+            pub kind: PollKind,
+            pub status_url: url::Url,
+            pub resource_url: url::Url,
+            pub location_url: Option<url::Url>,
+            pub retry_after: std::time::Duration,
+        }
+
+        impl Poller {
+            pub fn from_response(resource_url: &url::Url, rsp: &http::Response<bytes::Bytes>) -> Option<Self> {
+                let async_operation_url = header_url(rsp, "azure-asyncoperation");
+                let location_url = header_url(rsp, "location");
+                let (kind, status_url) = match (async_operation_url, &location_url) {
+                    (Some(async_operation_url), _) => (PollKind::AsyncOperation, async_operation_url),
+                    (None, Some(location_url)) => (PollKind::Location, location_url.clone()),
+                    (None, None) => return None,
+                };
+                Some(Self {
+                    kind,
+                    status_url,
+                    resource_url: resource_url.clone(),
+                    location_url,
+                    retry_after: retry_after(rsp).unwrap_or(DEFAULT_RETRY_AFTER),
+                })
+            }
+        }
+
+        const DEFAULT_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(5);
+
+        fn header_url(rsp: &http::Response<bytes::Bytes>, name: &str) -> Option<url::Url> {
+            rsp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| url::Url::parse(value).ok())
+        }
+
+        fn retry_after(rsp: &http::Response<bytes::Bytes>) -> Option<std::time::Duration> {
+            rsp.headers()
+                .get(http::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(std::time::Duration::from_secs)
+        }
+
+        async fn get(config: &crate::OperationConfig, url: &url::Url) -> std::result::Result<http::Response<bytes::Bytes>, Error> {
+            let mut url = url.clone();
+            let mut req_builder = http::request::Builder::new();
+            req_builder = req_builder.method(http::Method::GET);
+            #ts_auth
+            req_builder = req_builder.uri(url.as_str());
+            let req_body = bytes::Bytes::from_static(azure_core::EMPTY_BODY);
+            let req = req_builder.body(req_body).map_err(|source| Error::BuildRequestError{source})?;
+            crate::execute_request(config, req).await.map_err(|source| Error::ExecuteRequestError{source})
+        }
+
+        /// Polls a long-running operation until it reaches a terminal state, returning the final response.
+        /// The initial response is returned as is if it does not start a long-running operation.
+        pub async fn poll_until_done(
+            config: &crate::OperationConfig,
+            resource_url: &url::Url,
+            initial_response: http::Response<bytes::Bytes>,
+        ) -> std::result::Result<http::Response<bytes::Bytes>, Error> {
+            match initial_response.status() {
+                http::StatusCode::CREATED | http::StatusCode::ACCEPTED => {}
+                _ => return Ok(initial_response),
+            }
+            let mut poller = match Poller::from_response(resource_url, &initial_response) {
+                Some(poller) => poller,
+                None => return Ok(initial_response),
+            };
+            loop {
+                futures_timer::Delay::new(poller.retry_after).await;
+                let rsp = get(config, &poller.status_url).await?;
+                poller.retry_after = retry_after(&rsp).unwrap_or(DEFAULT_RETRY_AFTER);
+                match poller.kind {
+                    PollKind::Location => {
+                        if rsp.status() != http::StatusCode::ACCEPTED {
+                            return Ok(rsp);
+                        }
+                    }
+                    PollKind::AsyncOperation => {
+                        let rsp_body = rsp.body();
+                        let status: serde_json::Value =
+                            serde_json::from_slice(rsp_body).map_err(|source| Error::DeserializeError { source, body: rsp_body.clone() })?;
+                        let status = status.get("status").and_then(|status| status.as_str()).unwrap_or_default().to_owned();
+                        match status.as_str() {
+                            "Succeeded" => {
+                                #succeeded
+                            }
+                            "Failed" | "Canceled" => {
+                                return Err(Error::LongRunningOperationError { status, body: rsp_body.clone() });
+                            }
+                            _ => {}
+                        }
+                    }
+                }
             }
         }
+    }
+}
+
+/// For an `x-ms-pageable` operation with a `nextLinkName`, gets the response field with the next link
+/// and whether that field is required.
+fn get_pageable_next_link(
+    cg: &CodeGen,
+    doc_file: &Path,
+    operation_verb: &OperationVerb,
+    rsp: &Response,
+) -> Result<Option<(TokenStream, bool)>> {
+    let next_link_name = match &operation_verb.operation().x_ms_pageable {
+        Some(pageable) => match &pageable.next_link_name {
+            Some(next_link_name) => next_link_name,
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    let schema = match &rsp.schema {
+        Some(schema) => cg
+            .spec
+            .resolve_schema(doc_file, schema)
+            .map_err(|source| Error::SpecError { source })?,
+        None => return Ok(None),
+    };
+    if !schema.schema.properties.contains_key(next_link_name) {
+        return Ok(None);
+    }
+    let next_link = to_field_name(next_link_name, &cg.config.remap_reserved).map_err(|source| Error::IdentError {
+        source,
+        file: file!(),
+        line: line!(),
+    })?;
+    Ok(Some((next_link, schema.schema.required.contains(next_link_name))))
+}
+
+/// For a response that is a list of `value` with a `nextLink`, gets the response field with the next link
+/// and whether that field is required.
+fn get_list_next_link(cg: &CodeGen, doc_file: &Path, rsp: &Response) -> Result<Option<(TokenStream, bool)>> {
+    let schema = match &rsp.schema {
+        Some(schema) => cg
+            .spec
+            .resolve_schema(doc_file, schema)
+            .map_err(|source| Error::SpecError { source })?,
+        None => return Ok(None),
+    };
+    let value = match schema.schema.properties.get("value") {
+        Some(value) => cg
+            .spec
+            .resolve_schema(doc_file, value)
+            .map_err(|source| Error::SpecError { source })?,
+        None => return Ok(None),
     };
-    Ok(TokenStream::from(func))
+    let next_link_name = &"nextLink".to_owned();
+    if !is_array(&value.common) || !schema.schema.properties.contains_key(next_link_name) {
+        return Ok(None);
+    }
+    let next_link = to_field_name(next_link_name, &cg.config.remap_reserved).map_err(|source| Error::IdentError {
+        source,
+        file: file!(),
+        line: line!(),
+    })?;
+    Ok(Some((next_link, schema.schema.required.contains(next_link_name))))
+}
+
+/// Adds the `'a` lifetime to each reference in the tokens.
+fn with_lifetime(ts: TokenStream) -> TokenStream {
+    let mut lifetime_ts = TokenStream::new();
+    for tt in ts {
+        match tt {
+            TokenTree::Punct(punct) if punct.as_char() == '&' => lifetime_ts.extend(quote! { &'a }),
+            TokenTree::Group(group) => {
+                let group = Group::new(group.delimiter(), with_lifetime(group.stream()));
+                lifetime_ts.extend(quote! { #group });
+            }
+            tt => lifetime_ts.extend(quote! { #tt }),
+        }
+    }
+    lifetime_ts
 }
 
 pub fn create_mod(api_version: &str) -> TokenStream {
     quote! {
         pub mod models;
         pub mod operations;
+        #[cfg(test)]
+        mod examples;
         pub const API_VERSION: &str = #api_version;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_types() {
+        let tokens = quote! {
+            pub struct Foo {}
+            pub mod foo {
+                pub enum Kind { A, B }
+            }
+            pub type Bar = String;
+        };
+        assert_eq!(count_types(&tokens), 2);
+    }
+
+    #[test]
+    fn test_create_struct_builder() -> Result<()> {
+        let fields = [
+            StructField {
+                name: quote! { id },
+                tp: quote! { String },
+                is_required: true,
+            },
+            StructField {
+                name: quote! { tags },
+                tp: quote! { Option<String> },
+                is_required: false,
+            },
+            StructField {
+                name: quote! { build },
+                tp: quote! { Option<String> },
+                is_required: false,
+            },
+        ];
+        let builder = create_struct_builder(&quote! { FooBarBuilder }, &quote! { FooBar }, &fields)?.to_string();
+        assert!(builder.contains(&quote! { pub struct FooBarBuilder }.to_string()));
+        // the setters take the builder by value, so that they chain with `build`
+        assert!(builder.contains(&quote! { pub fn tags(mut self, tags: impl Into<Option<String> >) -> Self }.to_string()));
+        assert!(builder.contains(&quote! { pub fn build(self) }.to_string()));
+        assert!(builder.contains(&quote! { pub fn build_(mut self, build: impl Into<Option<String> >) -> Self }.to_string()));
+        Ok(())
+    }
+
+    fn get_property(schema: serde_json::Value) -> ResolvedSchema {
+        ResolvedSchema {
+            ref_key: None,
+            schema: serde_json::from_value(schema).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_get_serde_as_type() {
+        let date_time = get_property(serde_json::json!({ "type": "string", "format": "date-time" }));
+        assert_eq!(get_serde_as_type(&date_time), Some("Rfc3339"));
+        let uuid = get_property(serde_json::json!({ "type": "string", "format": "uuid" }));
+        assert_eq!(get_serde_as_type(&uuid), Some("serde_with::DisplayFromStr"));
+        let string = get_property(serde_json::json!({ "type": "string" }));
+        assert_eq!(get_serde_as_type(&string), None);
+    }
+
+    #[test]
+    fn test_add_used_names() {
+        let mut names = HashSet::new();
+        add_used_names(
+            &quote! {
+                #[serde_as(as = "Option<Rfc3339>")]
+                pub created: Option<DateTime>,
+            },
+            &mut names,
+        );
+        assert!(names.contains("Rfc3339"));
+        assert!(names.contains("DateTime"));
+    }
+
+    /// Defines the items and keeps their source, to compare with the generated code
+    macro_rules! with_source {
+        ($source:ident, $($item:item)*) => {
+            $($item)*
+            const $source: &str = stringify!($($item)*);
+        };
+    }
+
+    #[test]
+    fn test_create_enum_serde() -> Result<(), serde_json::Error> {
+        #[derive(Debug, PartialEq)]
+        enum Priority {
+            _0,
+            _1,
+        }
+        with_source! { SOURCE,
+            impl serde::Serialize for Priority {
+                fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    let value = match self {
+                        Self::_0 => serde_json::json!(0),
+                        Self::_1 => serde_json::json!(1),
+                    };
+                    serde::Serialize::serialize(&value, serializer)
+                }
+            }
+            impl<'de> serde::Deserialize<'de> for Priority {
+                fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+                    if value == serde_json::json!(0) {
+                        return Ok(Self::_0);
+                    }
+                    if value == serde_json::json!(1) {
+                        return Ok(Self::_1);
+                    }
+                    Err(serde::de::Error::custom(format!("unknown enum value {}", value)))
+                }
+            }
+        }
+        let (zero, one) = (serde_json::json!(0), serde_json::json!(1));
+        let generated = create_enum_serde(&quote! { Priority }, &[(quote! { _0 }, &zero), (quote! { _1 }, &one)]);
+        assert_eq!(generated.to_string(), SOURCE.parse::<TokenStream>().unwrap().to_string());
+
+        assert_eq!(serde_json::to_string(&Priority::_1)?, "1");
+        assert_eq!(serde_json::from_str::<Priority>("1")?, Priority::_1);
+        assert_eq!(
+            serde_json::from_str::<Priority>(&serde_json::to_string(&Priority::_0)?)?,
+            Priority::_0
+        );
+        assert!(serde_json::from_str::<Priority>("\"1\"").is_err());
+        assert!(serde_json::from_str::<Priority>("2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_json_value() {
+        assert_eq!(create_json_value(&serde_json::json!(1)).to_string(), "1");
+        assert_eq!(create_json_value(&serde_json::json!(-1)).to_string(), quote! { -1 }.to_string());
+        assert_eq!(create_json_value(&serde_json::json!(true)).to_string(), "true");
+        assert_eq!(create_json_value(&serde_json::json!("a")).to_string(), "\"a\"");
+    }
+
+    #[test]
+    fn test_create_doc_lines() {
+        let description = "Gets a blob.\n    var blob = client.Get();\n```csharp\nvar blob = client.Get();\n```\nDone.";
+        assert_eq!(
+            create_doc_lines(description),
+            [
+                "Gets a blob.",
+                "var blob = client.Get();",
+                "```text",
+                "var blob = client.Get();",
+                "```",
+                "Done."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_path() {
+        let param_re = Regex::new(r"\{(\w+)\}").unwrap();
+        assert_eq!(
+            format_path(
+                &param_re,
+                "/subscriptions/{subscriptionId}/providers/Microsoft.Compute/virtualMachines"
+            ),
+            "/subscriptions/{}/providers/Microsoft.Compute/virtualMachines"
+        );
+        assert_eq!(
+            format_path(&param_re, "/a b/{name}/{resource-name}"),
+            "/a%20b/{}/%7Bresource-name%7D"
+        );
+    }
+
+    #[test]
+    fn test_create_fpath() {
+        let param_re = Regex::new(r"\{(\w+)\}").unwrap();
+        assert_eq!(create_fpath(&param_re, "/providers/{name}"), "{}/providers/{}");
+        assert_eq!(create_fpath(&param_re, "providers/{name}"), "{}/providers/{}");
+    }
+
+    #[test]
+    fn test_api_version_query_without_token() {
+        let query = create_api_version_query(true, Some("2020-06-01")).to_string();
+        assert_eq!(
+            query,
+            quote! { url.query_pairs_mut().append_pair("api-version", config.api_version()); }.to_string()
+        );
+        assert!(!query.contains("token"));
+        assert!(create_api_version_query(false, Some("2020-06-01")).is_empty());
+        assert!(create_api_version_query(true, None).is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+//! Formats the generated code with `rustfmt`, if it is on the `PATH`.
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The max width of the generated code, which matches the rustfmt.toml of this repo
+const MAX_WIDTH: usize = 140;
+
+/// Formats the code, or returns it unformatted if `rustfmt` is not found or fails
+pub fn format_code(code: String) -> String {
+    format_code_with_config(code, None)
+}
+
+/// Formats the code with the rustfmt.toml at the config path, instead of the default max width
+pub fn format_code_with_config(code: String, config_path: Option<&Path>) -> String {
+    match run_rustfmt(&code, config_path) {
+        Some(formatted) => formatted,
+        None => code,
+    }
+}
+
+/// Pipes the code through `rustfmt --edition 2018`
+fn run_rustfmt(code: &str, config_path: Option<&Path>) -> Option<String> {
+    let mut command = Command::new("rustfmt");
+    command.arg("--edition").arg("2018");
+    match config_path {
+        Some(config_path) => command.arg("--config-path").arg(config_path),
+        None => command.arg("--config").arg(format!("max_width={}", MAX_WIDTH)),
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // rustfmt reads all of stdin before writing, so the write does not block on the output
+    child.stdin.take()?.write_all(code.as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_code_invalid() {
+        // rustfmt fails on invalid code, or may not be installed
+        let code = "fn {".to_owned();
+        assert_eq!(format_code(code.clone()), code);
+    }
+
+    #[test]
+    fn test_format_code_missing_config() {
+        let code = "fn main() {}\n".to_owned();
+        assert_eq!(
+            format_code_with_config(code.clone(), Some(Path::new("does/not/exist/rustfmt.toml"))),
+            code
+        );
+    }
+}
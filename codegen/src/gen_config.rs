@@ -0,0 +1,137 @@
+//! The services, tags, and boxed properties of a generator like `gen_mgmt`, which may be read from a TOML file.
+//!
+//! ```toml
+//! [skip_services]
+//! netapp = "Ident 10minutely"
+//!
+//! [skip_tags]
+//! synapse = ["package-2020-12-01", "package-2021-03"]
+//!
+//! [box_properties]
+//! "../azure-rest-api-specs/specification/cosmos-db/resource-manager/Microsoft.DocumentDB/stable/2021-03-15/cosmos-db.json" = ["ContainerPartitionKey.kind"]
+//! ```
+
+use crate::PropertyName;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not read config file {}: {}", file.display(), source)]
+    ReadConfigFileError { file: PathBuf, source: std::io::Error },
+    #[error("Could not parse config file {}: {}", file.display(), source)]
+    ParseConfigFileError { file: PathBuf, source: toml::de::Error },
+    #[error("box property {} is not of the form Schema.property", property)]
+    BoxPropertyError { property: String },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GenConfig {
+    /// The services to skip, with the reason
+    pub skip_services: BTreeMap<String, String>,
+    /// The tags to skip for each service
+    pub skip_tags: BTreeMap<String, Vec<String>>,
+    /// The `Schema.property` names to box for each input file
+    pub box_properties: BTreeMap<String, Vec<String>>,
+}
+
+impl GenConfig {
+    /// Read the config file, which is `None` if it does not exist
+    pub fn read(file: &Path) -> Result<Option<Self>> {
+        if !file.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(file).map_err(|source| Error::ReadConfigFileError {
+            source,
+            file: file.to_owned(),
+        })?;
+        Self::parse(&text).map(Some).map_err(|source| Error::ParseConfigFileError {
+            source,
+            file: file.to_owned(),
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn skip_service(&self, service: &str) -> bool {
+        self.skip_services.contains_key(service)
+    }
+
+    pub fn skip_tag(&self, service: &str, tag: &str) -> bool {
+        self.skip_tags
+            .get(service)
+            .map_or(false, |tags| tags.iter().any(|skip| skip == tag))
+    }
+
+    /// The properties to box, as used by `Config::box_properties`
+    pub fn box_properties(&self) -> Result<HashSet<PropertyName>> {
+        let mut box_properties = HashSet::new();
+        for (file_path, properties) in &self.box_properties {
+            for property in properties {
+                let (schema_name, property_name) = property.split_once('.').ok_or_else(|| Error::BoxPropertyError {
+                    property: property.clone(),
+                })?;
+                box_properties.insert(PropertyName {
+                    file_path: PathBuf::from(file_path),
+                    schema_name: schema_name.to_owned(),
+                    property_name: property_name.to_owned(),
+                });
+            }
+        }
+        Ok(box_properties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = GenConfig::parse(
+            r#"
+            [skip_services]
+            netapp = "Ident 10minutely"
+
+            [skip_tags]
+            synapse = ["package-2020-12-01"]
+
+            [box_properties]
+            "specification/cosmos-db.json" = ["ContainerPartitionKey.kind"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.skip_service("netapp"));
+        assert!(!config.skip_service("compute"));
+        assert!(config.skip_tag("synapse", "package-2020-12-01"));
+        assert!(!config.skip_tag("synapse", "package-2021-03"));
+        let box_properties = config.box_properties().unwrap();
+        assert!(box_properties.contains(&PropertyName {
+            file_path: PathBuf::from("specification/cosmos-db.json"),
+            schema_name: "ContainerPartitionKey".to_owned(),
+            property_name: "kind".to_owned(),
+        }));
+    }
+
+    #[test]
+    fn test_parse_gen_mgmt() {
+        let config = GenConfig::parse(include_str!("../examples/gen_mgmt.toml")).unwrap();
+        assert!(config.skip_service("netapp"));
+        assert!(config.skip_tag("datamigration", "package-2018-04-19"));
+        assert_eq!(config.box_properties().unwrap().len(), 14);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(GenConfig::parse("").unwrap(), GenConfig::default());
+    }
+}
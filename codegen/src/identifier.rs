@@ -1,6 +1,7 @@
-use heck::CamelCase;
+use heck::{CamelCase, SnakeCase};
 use proc_macro2::TokenStream;
 use quote::ToTokens;
+use std::collections::HashMap;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug, thiserror::Error)]
@@ -31,17 +32,63 @@ impl CamelCaseIdent for str {
 }
 
 pub fn ident(text: &str) -> Result<TokenStream> {
-    let mut txt = replace_chars_with_underscore(text);
+    parse_ident(&prefix_with_underscore_keywords(&normalize(text)))
+}
+
+/// The characters of an identifier that are not allowed, replaced, before checking for a keyword
+fn normalize(text: &str) -> String {
+    let mut txt = replace_non_ascii(text);
+    txt = replace_chars_with_underscore(&txt);
     txt = remove_spaces(&txt);
-    txt = prefix_with_underscore_if_starts_with_number(&txt);
-    txt = prefix_with_underscore_keywords(&txt);
-    let idt = syn::parse_str::<syn::Ident>(&txt).map_err(|source| Error::ParseIdentError {
+    prefix_with_underscore_if_starts_with_number(&txt)
+}
+
+fn parse_ident(txt: &str) -> Result<TokenStream> {
+    let idt = syn::parse_str::<syn::Ident>(txt).map_err(|source| Error::ParseIdentError {
         source,
         text: txt.to_owned(),
     })?;
     Ok(idt.into_token_stream())
 }
 
+/// The snake case identifier for a module, field, or function name
+pub fn to_module_name(text: &str) -> Result<TokenStream> {
+    ident(&text.to_snake_case())
+}
+
+/// The camel case identifier for a type or enum variant name
+pub fn to_type_name(text: &str) -> Result<TokenStream> {
+    ident(&text.to_camel_case())
+}
+
+/// Like `ident`, but a reserved word found in the `remap_reserved` table is replaced
+/// with its mapped name instead of being suffixed with an underscore.
+pub fn ident_with_remap(text: &str, remap_reserved: &HashMap<String, String>) -> Result<TokenStream> {
+    let txt = normalize(text);
+    if is_keyword(&txt) {
+        if let Some(remapped) = remap_reserved.get(&txt) {
+            return ident(remapped);
+        }
+    }
+    parse_ident(&prefix_with_underscore_keywords(&txt))
+}
+
+/// The snake case identifier for a struct field or a function parameter, with the reserved words remapped
+pub fn to_field_name(text: &str, remap_reserved: &HashMap<String, String>) -> Result<TokenStream> {
+    ident_with_remap(&text.to_snake_case(), remap_reserved)
+}
+
+/// Suggested names for the reserved words, which are used if set as `Config::remap_reserved`
+pub const REMAP_RESERVED: &[(&str, &str)] = &[("type", "resource_type"), ("self", "this"), ("ref", "reference")];
+
+/// The `REMAP_RESERVED` table as a map
+pub fn remap_reserved() -> HashMap<String, String> {
+    REMAP_RESERVED
+        .iter()
+        .map(|(reserved, remapped)| (reserved.to_string(), remapped.to_string()))
+        .collect()
+}
+
 fn remove_spaces(text: &str) -> String {
     text.replace(" ", "")
 }
@@ -54,6 +101,56 @@ fn replace_chars_with_underscore(text: &str) -> String {
     txt
 }
 
+/// Transliterate the non-ASCII characters, or encode them like `_u00e4_` if there is no transliteration
+fn replace_non_ascii(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_owned();
+    }
+    let mut txt = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii() {
+            txt.push(ch);
+        } else {
+            match transliterate(ch) {
+                Some(ascii) => txt.push_str(ascii),
+                None => txt.push_str(&format!("_u{:04x}_", ch as u32)),
+            }
+        }
+    }
+    eprintln!("WARN transliterated identifier {} to {}", text, txt);
+    txt
+}
+
+fn transliterate(ch: char) -> Option<&'static str> {
+    let ascii = match ch {
+        'ä' | 'æ' => "ae",
+        'Ä' | 'Æ' => "Ae",
+        'ö' | 'ø' | 'œ' => "oe",
+        'Ö' | 'Ø' | 'Œ' => "Oe",
+        'ü' => "ue",
+        'Ü' => "Ue",
+        'ß' => "ss",
+        'à' | 'á' | 'â' | 'ã' | 'å' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' => "A",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' => "E",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'ò' | 'ó' | 'ô' | 'õ' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' => "O",
+        'ù' | 'ú' | 'û' => "u",
+        'Ù' | 'Ú' | 'Û' => "U",
+        'ç' => "c",
+        'Ç' => "C",
+        'ñ' => "n",
+        'Ñ' => "N",
+        // dashes and non-breaking spaces
+        '–' | '—' | '\u{a0}' => "_",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
 /// Replace some special charaters with their unicode names
 fn replace_chars_with_unicode_names(text: &str) -> String {
     text.replace("*", "Asterisk")
@@ -77,8 +174,11 @@ fn prefix_with_underscore_if_starts_with_number(text: &str) -> String {
     }
 }
 
+/// A weak keyword is escaped as a raw identifier, since it is only reserved in some contexts
 fn prefix_with_underscore_keywords(text: &str) -> String {
-    if is_keyword(&text) {
+    if is_weak_keyword(text) {
+        format!("r#{}", text)
+    } else if is_keyword(&text) {
         format!("{}_", text)
     } else {
         text.to_owned()
@@ -92,6 +192,8 @@ fn is_keyword(word: &str) -> bool {
         "abstract"
             | "alignof"
             | "as"
+            | "async"
+            | "await"
             | "become"
             | "box"
             | "break"
@@ -99,6 +201,7 @@ fn is_keyword(word: &str) -> bool {
             | "continue"
             | "crate"
             | "do"
+            | "dyn"
             | "else"
             | "enum"
             | "extern"
@@ -132,6 +235,7 @@ fn is_keyword(word: &str) -> bool {
             | "super"
             | "trait"
             | "true"
+            | "try"
             | "type"
             | "typeof"
             | "unsafe"
@@ -141,13 +245,19 @@ fn is_keyword(word: &str) -> bool {
             | "where"
             | "while"
             | "yield"
+            // https://doc.rust-lang.org/reference/keywords.html#weak-keywords
+            | "union"
+            | "macro_rules"
     )
 }
 
+fn is_weak_keyword(word: &str) -> bool {
+    matches!(word, "union" | "macro_rules")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use heck::SnakeCase;
 
     #[test]
     fn test_odata_next_link() -> Result<()> {
@@ -158,6 +268,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_to_module_name_and_to_type_name() -> Result<()> {
+        assert_eq!(to_module_name("VirtualMachines")?.to_string(), "virtual_machines");
+        assert_eq!(to_module_name("Type")?.to_string(), "type_");
+        assert_eq!(to_type_name("virtual-machine")?.to_string(), "VirtualMachine");
+        Ok(())
+    }
+
+    #[test]
+    fn test_keywords() -> Result<()> {
+        assert_eq!(ident("async")?.to_string(), "async_");
+        assert_eq!(ident("await")?.to_string(), "await_");
+        assert_eq!(ident("dyn")?.to_string(), "dyn_");
+        assert_eq!(ident("try")?.to_string(), "try_");
+        assert_eq!(ident("union")?.to_string(), "r#union");
+        assert_eq!(ident("macro_rules")?.to_string(), "r#macro_rules");
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_ascii() -> Result<()> {
+        assert_eq!(ident("größe")?.to_string(), "groesse");
+        assert_eq!(ident("Dev – Test")?.to_string(), "Dev_Test");
+        assert_eq!(ident("名前")?.to_string(), "_u540d__u524d_");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(ident("").is_err());
+    }
+
+    #[test]
+    fn test_ident_with_remap() -> Result<()> {
+        let remap = remap_reserved();
+        assert_eq!(ident_with_remap("type", &remap)?.to_string(), "resource_type");
+        assert_eq!(ident_with_remap("self", &remap)?.to_string(), "this");
+        assert_eq!(ident_with_remap("match", &remap)?.to_string(), "match_");
+        assert_eq!(ident_with_remap("type", &HashMap::new())?.to_string(), "type_");
+        assert_eq!(ident_with_remap("name", &remap)?.to_string(), "name");
+        assert_eq!(to_field_name("Type", &remap)?.to_string(), "resource_type");
+        assert_eq!(to_field_name("größe", &remap)?.to_string(), "groesse");
+        Ok(())
+    }
+
     #[test]
     fn test_three_dot_two() -> Result<()> {
         let idt = ident("3.2")?;
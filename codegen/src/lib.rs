@@ -1,9 +1,14 @@
+mod cache;
 pub mod cargo_toml;
 mod codegen;
 pub mod config_parser;
+pub mod format;
+pub mod gen_config;
 pub mod identifier;
 pub mod lib_rs;
+mod oas3;
 pub mod path;
+mod scc;
 pub mod spec;
 mod status_codes;
 
@@ -12,11 +17,14 @@ pub use self::{
     spec::{OperationVerb, ResolvedSchema, Spec},
 };
 
+use autorust_openapi::ReferenceOr;
+use cache::Cache;
+use codegen::{count_types, get_item_names};
 use config_parser::Configuration;
 use proc_macro2::TokenStream;
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::{self, File},
     io::prelude::*,
     path::{Path, PathBuf},
@@ -38,12 +46,18 @@ pub enum Error {
     CreateModelsError { source: codegen::Error, config: Config },
     #[error("CreateOperationsError")]
     CreateOperationsError { source: codegen::Error },
+    #[error("CreateExamplesError")]
+    CreateExamplesError { source: codegen::Error },
     #[error("PathError")]
     PathError { source: path::Error },
     #[error("IoError")]
     IoError { source: std::io::Error },
     #[error("file name was not utf-8")]
     FileNameNotUtf8Error {},
+    #[error("CacheError")]
+    CacheError { source: cache::Error },
+    #[error("{} unresolved references, the first is {}", errors.len(), errors[0])]
+    ValidationError { errors: Vec<spec::ValidationError> },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -59,46 +73,237 @@ pub struct Config {
     pub output_folder: PathBuf,
     pub api_version: Option<String>,
     pub box_properties: HashSet<PropertyName>,
+    /// Names to use for fields and parameters that are reserved words, instead of suffixing them with an underscore,
+    /// such as `identifier::remap_reserved()`
+    pub remap_reserved: HashMap<String, String>,
+    /// Also emit `type Configuration = OperationConfig` for code written against the older generated signatures
+    pub aliased_config_type: bool,
+    /// Group the optional query params of each operation into an `Options` struct
+    pub group_query_options: bool,
+    /// Also emit a `_all` stream for list operations that return a `value` with a `nextLink`, without `x-ms-pageable`
+    pub generate_streams: bool,
+    /// Fail instead of warning when the spec has references that do not resolve
+    pub strict: bool,
+    /// Only write the generated files that changed since the last run, as recorded in `.autorust_cache` in the output folder
+    pub incremental: bool,
+    /// Generate the files without writing them
+    pub dry_run: bool,
+    /// Print all of the lint findings of the spec, instead of only the warnings
+    pub lint: bool,
+    /// The rustfmt.toml to format the generated files with
+    pub rustfmt_config_path: Option<PathBuf>,
 }
 
-pub fn run(config: Config) -> Result<()> {
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input_files: Vec::new(),
+            output_folder: PathBuf::new(),
+            api_version: None,
+            box_properties: HashSet::new(),
+            remap_reserved: HashMap::new(),
+            aliased_config_type: false,
+            group_query_options: false,
+            generate_streams: false,
+            strict: false,
+            incremental: false,
+            dry_run: false,
+            lint: false,
+            rustfmt_config_path: None,
+        }
+    }
+}
+
+/// The result of a run
+#[derive(Debug)]
+pub enum RunResult {
+    /// The generated files were written to the output folder
+    Written(WrittenResult),
+    /// The generated files were not written for a dry run
+    DryRun(DryRunResult),
+}
+
+/// What a run wrote
+#[derive(Debug)]
+pub struct WrittenResult {
+    /// The files that were written, not including those that were unchanged
+    pub files: Vec<PathBuf>,
+    /// The number of structs and enums in the models
+    pub types: usize,
+    /// The number of operations defined in the input files
+    pub operations: usize,
+}
+
+/// The files that a run would write
+#[derive(Debug)]
+pub struct DryRunResult {
+    pub files: Vec<(PathBuf, TokenStream)>,
+    /// The number of schemas defined in the input files
+    pub models: usize,
+    /// The number of operations defined in the input files
+    pub operations: usize,
+}
+
+/// A phase of a run, as reported to the callback of `run_with_progress`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Progress {
+    LoadingSpec,
+    GeneratingModels,
+    GeneratingOperations,
+    GeneratingExamples,
+    WritingFile(PathBuf),
+}
+
+pub fn run(config: Config) -> Result<RunResult> {
+    run_with_progress(config, |_| {})
+}
+
+/// Runs the same as `run`, calling `on_progress` at the start of each phase
+pub fn run_with_progress(config: Config, on_progress: impl Fn(Progress)) -> Result<RunResult> {
     let directory = &config.output_folder;
-    fs::create_dir_all(directory).map_err(|source| Error::CreateOutputDirectoryError {
-        source,
-        directory: directory.into(),
-    })?;
+    on_progress(Progress::LoadingSpec);
     let cg = &CodeGen::new(config.clone()).map_err(|source| Error::CodeGenNewError { source })?;
 
+    // the info findings, like unreferenced definitions, are many in the larger specs
+    for warning in cg.spec.lint() {
+        if config.lint || warning.severity >= spec::LintSeverity::Warning {
+            eprintln!("{}", warning);
+        }
+    }
+
+    // check the references before generating code
+    let errors = cg.spec.validate();
+    if errors.len() > 0 {
+        if config.strict {
+            return Err(Error::ValidationError { errors });
+        }
+        for error in &errors {
+            eprintln!("WARN {}", error);
+        }
+    }
+
+    let mut files = Vec::new();
+
     // create models from schemas
+    on_progress(Progress::GeneratingModels);
     let models = cg.create_models().map_err(|source| Error::CreateModelsError {
         source,
         config: config.clone(),
     })?;
+    let types = count_types(&models);
+    let model_names = get_item_names(&models);
     let models_path = path::join(&config.output_folder, "models.rs").map_err(|source| Error::PathError { source })?;
-    write_file(&models_path, &models)?;
+    files.push((models_path, models));
 
     // create api client from operations
-    let operations = cg.create_operations().map_err(|source| Error::CreateOperationsError { source })?;
+    on_progress(Progress::GeneratingOperations);
+    let operations = cg
+        .create_operations(&model_names)
+        .map_err(|source| Error::CreateOperationsError { source })?;
     let operations_path = path::join(&config.output_folder, "operations.rs").map_err(|source| Error::PathError { source })?;
-    write_file(&operations_path, &operations)?;
+    files.push((operations_path, operations));
+
+    // create tests from the examples
+    on_progress(Progress::GeneratingExamples);
+    let examples = cg.create_examples().map_err(|source| Error::CreateExamplesError { source })?;
+    let examples_path = path::join(&config.output_folder, "examples.rs").map_err(|source| Error::PathError { source })?;
+    files.push((examples_path, examples));
 
     if let Some(api_version) = &config.api_version {
         let operations = create_mod(api_version);
         let operations_path = path::join(&config.output_folder, "mod.rs").map_err(|source| Error::PathError { source })?;
-        write_file(&operations_path, &operations)?;
+        files.push((operations_path, operations));
+    }
+
+    let operations = cg
+        .spec
+        .input_docs()
+        .flat_map(|(_, doc)| doc.paths.values())
+        .map(|item| match item {
+            ReferenceOr::Item(item) => spec::path_item_operations(item).count(),
+            ReferenceOr::Reference { .. } => 0,
+        })
+        .sum();
+
+    if config.dry_run {
+        let models = cg.spec.input_docs().map(|(_, doc)| doc.definitions.len()).sum();
+        return Ok(RunResult::DryRun(DryRunResult { files, models, operations }));
+    }
+
+    fs::create_dir_all(directory).map_err(|source| Error::CreateOutputDirectoryError {
+        source,
+        directory: directory.into(),
+    })?;
+
+    // any change to the input files invalidates the cached outputs
+    let mut cache = if config.incremental {
+        let mut cache = Cache::read(directory);
+        let input_files: Vec<&PathBuf> = cg.spec.docs().keys().collect();
+        cache.set_input_files(&input_files).map_err(|source| Error::CacheError { source })?;
+        Some(cache)
+    } else {
+        None
+    };
+    let mut written = Vec::new();
+    for (file, tokens) in &files {
+        on_progress(Progress::WritingFile(file.clone()));
+        if write_output(&mut cache, file, tokens, config.rustfmt_config_path.as_deref())? {
+            written.push(file.clone());
+        }
+    }
+    if let Some(cache) = &cache {
+        cache.write(directory).map_err(|source| Error::CacheError { source })?;
     }
-    Ok(())
+    Ok(RunResult::Written(WrittenResult {
+        files: written,
+        types,
+        operations,
+    }))
 }
 
-fn write_file<P: AsRef<Path>>(file: P, tokens: &TokenStream) -> Result<()> {
+/// Writes the file, unless the cache shows it is unchanged, returning whether it was written
+fn write_output(cache: &mut Option<Cache>, file: &Path, tokens: &TokenStream, rustfmt_config_path: Option<&Path>) -> Result<bool> {
+    if let Some(cache) = cache {
+        if !cache.update(file, &tokens.to_string()) {
+            return Ok(false);
+        }
+    }
+    write_file(file, tokens, rustfmt_config_path)
+}
+
+/// Writes the formatted code, unless the file already has it, returning whether it was written
+fn write_file<P: AsRef<Path>>(file: P, tokens: &TokenStream, rustfmt_config_path: Option<&Path>) -> Result<bool> {
     let file = file.as_ref();
     // println!("writing file {}", &file.display());
-    let code = tokens.to_string();
-    let mut buffer = File::create(&file).map_err(|source| Error::CreateFileError { source, file: file.into() })?;
-    buffer
-        .write_all(&code.as_bytes())
-        .map_err(|source| Error::WriteFileError { source, file: file.into() })?;
-    Ok(())
+    let code = format::format_code_with_config(tokens.to_string(), rustfmt_config_path);
+    // an unchanged file is not written, so that its modified time does not trigger a rebuild
+    if let Ok(existing) = fs::read_to_string(file) {
+        if existing == code {
+            return Ok(false);
+        }
+    }
+    // the code is written to a temporary file that is renamed, so an interrupted write does not leave a partial file
+    let temp_file = get_temp_file(file);
+    let mut buffer = File::create(&temp_file).map_err(|source| Error::CreateFileError {
+        source,
+        file: temp_file.clone(),
+    })?;
+    buffer.write_all(&code.as_bytes()).map_err(|source| Error::WriteFileError {
+        source,
+        file: temp_file.clone(),
+    })?;
+    drop(buffer);
+    fs::rename(&temp_file, file).map_err(|source| {
+        let _ = fs::remove_file(&temp_file);
+        Error::WriteFileError { source, file: file.into() }
+    })?;
+    Ok(true)
+}
+
+/// A temporary file in the same folder, since a rename is only atomic within a file system
+fn get_temp_file(file: &Path) -> PathBuf {
+    let file_name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    file.with_file_name(format!(".{}.tmp", file_name))
 }
 
 const SPEC_FOLDER: &str = "../azure-rest-api-specs/specification";
@@ -1,6 +1,7 @@
 use crate::{codegen::create_generated_by_header, identifier::ident, write_file};
 use proc_macro2::TokenStream;
 use quote::quote;
+use regex::Regex;
 
 use std::path::Path;
 
@@ -19,12 +20,29 @@ pub enum Error {
 }
 
 pub fn create(feature_mod_names: &Vec<(String, String)>, path: &Path) -> Result<()> {
-    write_file(path, &create_body(feature_mod_names)?).map_err(|source| Error::WriteFileError { source })?;
+    write_file(path, &create_body(feature_mod_names)?, None).map_err(|source| Error::WriteFileError { source })?;
     Ok(())
 }
 
+/// The date in the tag of a feature, such as 2020-06-01 in package-2020-06-01-preview
+fn get_tag_date(tag: &str) -> &str {
+    lazy_static::lazy_static! {
+        static ref DATE: Regex = Regex::new(r"\d{4}-\d{2}(-\d{2})?").unwrap();
+    }
+    DATE.find(tag).map_or("", |date| date.as_str())
+}
+
+/// Sorts the features by the dates in their tags, oldest first
+fn sort_by_tag_date(feature_mod_names: &[(String, String)]) -> Vec<&(String, String)> {
+    let mut sorted: Vec<_> = feature_mod_names.iter().collect();
+    sorted.sort_by(|a, b| get_tag_date(&a.0).cmp(get_tag_date(&b.0)).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
 fn create_body(feature_mod_names: &Vec<(String, String)>) -> Result<TokenStream> {
     let mut cfgs = TokenStream::new();
+    let mut api_versions = TokenStream::new();
+    let mut prelude = TokenStream::new();
     for (feature_name, mod_name) in feature_mod_names {
         let mod_name = ident(mod_name).map_err(|source| Error::IdentModNameError {
             source,
@@ -37,49 +55,204 @@ fn create_body(feature_mod_names: &Vec<(String, String)>) -> Result<TokenStream>
             #[cfg(feature = #feature_name)]
             pub use #mod_name::{models, operations, API_VERSION};
         });
+        prelude.extend(quote! {
+            #[cfg(feature = #feature_name)]
+            pub use super::#mod_name::{models::*, operations::*};
+        });
     }
+
+    // the latest stable version of the enabled features, then the latest preview
+    let sorted = sort_by_tag_date(feature_mod_names);
+    let (previews, stables): (Vec<_>, Vec<_>) = sorted.iter().rev().partition(|(feature_name, _)| feature_name.contains("preview"));
+    let mut latest_stable = TokenStream::new();
+    let mut newer_features: Vec<&str> = Vec::new();
+    for (feature_name, mod_name) in stables.into_iter().chain(previews) {
+        let mod_name = ident(mod_name).map_err(|source| Error::IdentModNameError {
+            source,
+            feature_name: feature_name.to_owned(),
+            mod_name: mod_name.to_owned(),
+        })?;
+        latest_stable.extend(quote! {
+            /// The latest API version that is not a preview, or the latest preview if there are only previews
+            #[cfg(all(feature = #feature_name, not(any(#(feature = #newer_features),*))))]
+            pub const LATEST_STABLE_API_VERSION: &str = #mod_name::API_VERSION;
+        });
+        newer_features.push(feature_name.as_str());
+    }
+    for (feature_name, mod_name) in sorted {
+        let mod_name = ident(mod_name).map_err(|source| Error::IdentModNameError {
+            source,
+            feature_name: feature_name.to_owned(),
+            mod_name: mod_name.to_owned(),
+        })?;
+        api_versions.extend(quote! {
+            #[cfg(feature = #feature_name)]
+            #mod_name::API_VERSION,
+        });
+    }
+
+    // there is no latest version when no feature is enabled
+    let feature_names: Vec<&str> = feature_mod_names.iter().map(|(feature_name, _)| feature_name.as_str()).collect();
+
     let generated_by = create_generated_by_header();
     Ok(quote! {
         #generated_by
         #cfgs
         use azure_core::setters;
 
+        /// The API versions of the enabled features, oldest first
+        pub const API_VERSIONS: &[&str] = &[#api_versions];
+
+        #latest_stable
+
+        /// The API versions of the enabled features
+        pub mod versions {
+            /// All the API versions, oldest first
+            pub fn all() -> &'static [&'static str] {
+                super::API_VERSIONS
+            }
+
+            /// The latest API version that is not a preview, or the latest preview if there are only previews
+            #[cfg(any(#(feature = #feature_names),*))]
+            pub fn latest_stable() -> &'static str {
+                super::LATEST_STABLE_API_VERSION
+            }
+        }
+
+        /// The commonly used types, to import with `use <crate>::prelude::*`
+        pub mod prelude {
+            #prelude
+            pub use super::{Credentials, ExponentialBackoff, HttpClient, OperationConfig, OperationConfigBuilder, RetryPolicy};
+        }
+
+        /// The HTTP client the operations execute requests with.
+        /// Implement it to use another HTTP library or a mock client for testing.
+        pub use azure_core::HttpClient;
+
+        /// How long the default HTTP client waits to connect
+        pub const DEFAULT_CONNECTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+        /// How long a request waits for its response
+        pub const DEFAULT_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+        /// The default HTTP client, which uses `reqwest`
+        pub fn default_http_client() -> std::result::Result<std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>, reqwest::Error> {
+            create_http_client(None, Some(DEFAULT_CONNECTION_TIMEOUT))
+        }
+
+        /// The default HTTP client, sending all requests through the proxy, if there is one
+        pub fn create_http_client(
+            proxy_url: Option<&str>,
+            connection_timeout: Option<std::time::Duration>,
+        ) -> std::result::Result<std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>, reqwest::Error> {
+            let mut client_builder = reqwest::Client::builder();
+            if let Some(proxy_url) = proxy_url {
+                client_builder = client_builder.proxy(reqwest::Proxy::all(proxy_url)?);
+            }
+            if let Some(connection_timeout) = connection_timeout {
+                client_builder = client_builder.connect_timeout(connection_timeout);
+            }
+            Ok(std::sync::Arc::new(std::boxed::Box::new(client_builder.build()?)))
+        }
+
+        /// The credentials that requests are authenticated with
+        pub enum Credentials {
+            /// Gets an OAuth2 bearer token for the `token_credential_resource`
+            TokenCredential(Box<dyn azure_core::TokenCredential>),
+            /// An OAuth2 bearer token
+            Bearer(String),
+            /// An API key, sent as the spec's `securityDefinitions` require
+            ApiKey(String),
+        }
+
+        impl Credentials {
+            pub fn bearer(token: String) -> Self {
+                Self::Bearer(token)
+            }
+            pub fn api_key(key: String) -> Self {
+                Self::ApiKey(key)
+            }
+        }
+
+        impl From<Box<dyn azure_core::TokenCredential>> for Credentials {
+            fn from(token_credential: Box<dyn azure_core::TokenCredential>) -> Self {
+                Self::TokenCredential(token_credential)
+            }
+        }
+
         pub fn config(
             http_client: std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>,
-            token_credential: Box<dyn azure_core::TokenCredential>,
+            credentials: impl Into<Credentials>,
         ) -> OperationConfigBuilder {
-            OperationConfigBuilder {
-                api_version: None,
-                http_client,
-                base_path: None,
-                token_credential,
-                token_credential_resource: None,
-            }
+            OperationConfig::builder(credentials).http_client(http_client)
         }
 
         pub struct OperationConfigBuilder {
             api_version: Option<String>,
-            http_client: std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>,
+            http_client: Option<std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>>,
             base_path: Option<String>,
-            token_credential: Box<dyn azure_core::TokenCredential>,
+            credentials: Credentials,
             token_credential_resource: Option<String>,
+            retry_policy: Option<Box<dyn RetryPolicy>>,
+            proxy_url: Option<String>,
+            connection_timeout: Option<std::time::Duration>,
+            request_timeout: Option<std::time::Duration>,
         }
 
         impl OperationConfigBuilder {
             setters! {
-                api_version: String => Some(api_version),
-                base_path: String => Some(base_path),
-                token_credential_resource: String => Some(token_credential_resource),
+                http_client: std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>> => Some(http_client),
+                retry_policy: Box<dyn RetryPolicy> => Some(retry_policy),
+                request_timeout: Option<std::time::Duration> => request_timeout,
             }
 
-            pub fn build(self) -> OperationConfig {
-                OperationConfig {
+            pub fn api_version(mut self, api_version: impl Into<String>) -> Self {
+                self.api_version = Some(api_version.into());
+                self
+            }
+
+            pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+                self.base_path = Some(base_path.into());
+                self
+            }
+
+            pub fn token_credential_resource(mut self, token_credential_resource: impl Into<String>) -> Self {
+                self.token_credential_resource = Some(token_credential_resource.into());
+                self
+            }
+
+            /// Sends the requests through the proxy. Only the default HTTP client uses it, not one given with `http_client`.
+            pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+                self.proxy_url = Some(proxy_url.into());
+                self
+            }
+
+            /// Sets how long to wait to connect. Only the default HTTP client uses it, not one given with `http_client`.
+            pub fn connection_timeout(mut self, connection_timeout: Option<std::time::Duration>) -> Self {
+                self.connection_timeout = connection_timeout;
+                self
+            }
+
+            /// Builds the config, creating the default HTTP client with the proxy and the connection timeout if no client was given
+            pub fn build(self) -> std::result::Result<OperationConfig, reqwest::Error> {
+                let http_client = match self.http_client {
+                    Some(http_client) => http_client,
+                    None => create_http_client(self.proxy_url.as_deref(), self.connection_timeout)?,
+                };
+                Ok(OperationConfig {
                     api_version: self.api_version.unwrap_or(API_VERSION.to_owned()),
-                    http_client: self.http_client,
-                    base_path: self.base_path.unwrap_or("https://management.azure.com".to_owned()),
-                    token_credential: Some(self.token_credential),
+                    http_client,
+                    base_path: self
+                        .base_path
+                        .map(|base_path| base_path.trim_end_matches('/').to_owned())
+                        .unwrap_or("https://management.azure.com".to_owned()),
+                    credentials: self.credentials,
                     token_credential_resource: self.token_credential_resource.unwrap_or("https://management.azure.com/".to_owned()),
-                }
+                    retry_policy: self.retry_policy,
+                    proxy_url: self.proxy_url,
+                    connection_timeout: self.connection_timeout,
+                    request_timeout: self.request_timeout,
+                })
             }
         }
 
@@ -87,11 +260,35 @@ fn create_body(feature_mod_names: &Vec<(String, String)>) -> Result<TokenStream>
             api_version: String,
             http_client: std::sync::Arc<std::boxed::Box<dyn azure_core::HttpClient>>,
             base_path: String,
-            token_credential: Option<Box<dyn azure_core::TokenCredential>>,
+            credentials: Credentials,
             token_credential_resource: String,
+            retry_policy: Option<Box<dyn RetryPolicy>>,
+            proxy_url: Option<String>,
+            connection_timeout: Option<std::time::Duration>,
+            request_timeout: Option<std::time::Duration>,
         }
 
         impl OperationConfig {
+            /// Starts building a config that uses the default HTTP client
+            pub fn builder(credentials: impl Into<Credentials>) -> OperationConfigBuilder {
+                OperationConfigBuilder {
+                    api_version: None,
+                    http_client: None,
+                    base_path: None,
+                    credentials: credentials.into(),
+                    token_credential_resource: None,
+                    retry_policy: None,
+                    proxy_url: None,
+                    connection_timeout: Some(DEFAULT_CONNECTION_TIMEOUT),
+                    request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+                }
+            }
+            /// Use an API version of one of the enabled features, or `None` if it is not enabled
+            pub fn with_version(mut self, version: &str) -> Option<OperationConfig> {
+                let version = versions::all().iter().find(|api_version| **api_version == version)?;
+                self.api_version = (*version).to_owned();
+                Some(self)
+            }
             pub fn api_version(&self) -> &str {
                 self.api_version.as_str()
             }
@@ -101,12 +298,172 @@ fn create_body(feature_mod_names: &Vec<(String, String)>) -> Result<TokenStream>
             pub fn base_path(&self) -> &str {
                 self.base_path.as_str()
             }
+            pub fn credentials(&self) -> &Credentials {
+                &self.credentials
+            }
             pub fn token_credential(&self) -> Option<&dyn azure_core::TokenCredential> {
-                self.token_credential.as_deref()
+                match &self.credentials {
+                    Credentials::TokenCredential(token_credential) => Some(token_credential.as_ref()),
+                    _ => None,
+                }
+            }
+            pub fn bearer_token(&self) -> Option<&str> {
+                match &self.credentials {
+                    Credentials::Bearer(token) => Some(token.as_str()),
+                    _ => None,
+                }
+            }
+            pub fn api_key(&self) -> Option<&str> {
+                match &self.credentials {
+                    Credentials::ApiKey(key) => Some(key.as_str()),
+                    _ => None,
+                }
             }
             pub fn token_credential_resource(&self) -> &str {
                 self.token_credential_resource.as_str()
             }
+            pub fn retry_policy(&self) -> Option<&dyn RetryPolicy> {
+                self.retry_policy.as_deref()
+            }
+            pub fn proxy_url(&self) -> Option<&str> {
+                self.proxy_url.as_deref()
+            }
+            /// Sends the requests through the proxy, replacing the HTTP client with the default one
+            pub fn set_proxy(&mut self, proxy_url: String) -> std::result::Result<(), reqwest::Error> {
+                self.http_client = create_http_client(Some(&proxy_url), self.connection_timeout)?;
+                self.proxy_url = Some(proxy_url);
+                Ok(())
+            }
+            /// How long the default HTTP client waits to connect
+            pub fn connection_timeout(&self) -> Option<std::time::Duration> {
+                self.connection_timeout
+            }
+            /// How long each attempt of a request waits for its response
+            pub fn request_timeout(&self) -> Option<std::time::Duration> {
+                self.request_timeout
+            }
+        }
+
+        /// Decides if a request is retried and how long to wait before the next attempt.
+        /// The `status` is `None` and the `error` is `Some` when the request failed to execute.
+        pub trait RetryPolicy: Send + Sync {
+            fn should_retry(&self, attempt: u32, status: Option<u16>, error: Option<&(dyn std::error::Error + Sync + Send)>) -> bool;
+            fn wait_duration(&self, attempt: u32) -> std::time::Duration;
+        }
+
+        /// Retries failed requests and throttled or unavailable responses, doubling the wait after each attempt.
+        #[derive(Clone, Debug)]
+        pub struct ExponentialBackoff {
+            pub max_retries: u32,
+            pub base_ms: u64,
+        }
+
+        impl Default for ExponentialBackoff {
+            fn default() -> Self {
+                Self { max_retries: 3, base_ms: 800 }
+            }
+        }
+
+        impl RetryPolicy for ExponentialBackoff {
+            fn should_retry(&self, attempt: u32, status: Option<u16>, error: Option<&(dyn std::error::Error + Sync + Send)>) -> bool {
+                if attempt >= self.max_retries {
+                    return false;
+                }
+                match status {
+                    Some(status) => matches!(status, 408 | 429 | 500 | 502 | 503 | 504),
+                    None => error.is_some(),
+                }
+            }
+            fn wait_duration(&self, attempt: u32) -> std::time::Duration {
+                std::time::Duration::from_millis(self.base_ms.saturating_mul(1u64 << attempt.min(16)))
+            }
+        }
+
+        /// Deserializes an XML response body, which requires the `xml` feature
+        #[allow(dead_code)]
+        pub(crate) fn from_xml<T: serde::de::DeserializeOwned>(body: &[u8]) -> std::result::Result<T, Box<dyn std::error::Error + Sync + Send>> {
+            #[cfg(feature = "xml")]
+            {
+                serde_xml_rs::from_reader(body).map_err(|err| err.into())
+            }
+            #[cfg(not(feature = "xml"))]
+            {
+                let _ = body;
+                Err("an XML response requires the xml feature".into())
+            }
+        }
+
+        /// Executes the request with the HTTP client, failing if there is no response within the request timeout.
+        async fn execute_request_with_timeout(
+            config: &OperationConfig,
+            req: http::Request<bytes::Bytes>,
+        ) -> std::result::Result<http::Response<bytes::Bytes>, Box<dyn std::error::Error + Sync + Send>> {
+            let request_timeout = match config.request_timeout() {
+                Some(request_timeout) => request_timeout,
+                None => return config.http_client().execute_request(req).await,
+            };
+            let rsp = config.http_client().execute_request(req);
+            let timeout = futures_timer::Delay::new(request_timeout);
+            futures::pin_mut!(rsp);
+            match futures::future::select(rsp, timeout).await {
+                futures::future::Either::Left((rsp, _)) => rsp,
+                futures::future::Either::Right(_) => Err(format!("the request timed out after {:?}", request_timeout).into()),
+            }
+        }
+
+        /// Executes the request with the HTTP client, retrying as the retry policy allows.
+        pub(crate) async fn execute_request(
+            config: &OperationConfig,
+            req: http::Request<bytes::Bytes>,
+        ) -> std::result::Result<http::Response<bytes::Bytes>, Box<dyn std::error::Error + Sync + Send>> {
+            let retry_policy = match config.retry_policy() {
+                Some(retry_policy) => retry_policy,
+                None => return execute_request_with_timeout(config, req).await,
+            };
+            let (parts, body) = req.into_parts();
+            let mut attempt = 0;
+            loop {
+                let mut req_builder = http::request::Builder::new().method(parts.method.clone()).uri(parts.uri.clone());
+                for (name, value) in &parts.headers {
+                    req_builder = req_builder.header(name, value);
+                }
+                let req = req_builder.body(body.clone())?;
+                let rsp = execute_request_with_timeout(config, req).await;
+                let should_retry = match &rsp {
+                    Ok(rsp) => retry_policy.should_retry(attempt, Some(rsp.status().as_u16()), None),
+                    Err(err) => retry_policy.should_retry(attempt, None, Some(err.as_ref())),
+                };
+                if !should_retry {
+                    return rsp;
+                }
+                futures_timer::Delay::new(retry_policy.wait_duration(attempt)).await;
+                attempt += 1;
+            }
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_tag_date() {
+        let feature_mod_names: Vec<(String, String)> = [
+            ("package-2020-06-01", "package_2020_06_01"),
+            ("package-preview-2019-07", "package_preview_2019_07"),
+            ("package-2021-03-01-preview", "package_2021_03_01_preview"),
+        ]
+        .iter()
+        .map(|(feature, mod_name)| (feature.to_string(), mod_name.to_string()))
+        .collect();
+        let sorted: Vec<&str> = sort_by_tag_date(&feature_mod_names)
+            .iter()
+            .map(|(feature, _)| feature.as_str())
+            .collect();
+        assert_eq!(
+            sorted,
+            ["package-preview-2019-07", "package-2020-06-01", "package-2021-03-01-preview"]
+        );
+    }
+}
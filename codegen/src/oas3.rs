@@ -0,0 +1,346 @@
+//! Converts an OpenAPI 3.0 document into the OpenAPI 2.0 (Swagger) form used by `Spec`.
+//!
+//! This maps `components/schemas` to `definitions`, `components/parameters` to `parameters`,
+//! a `requestBody` to a body parameter, and response `content` to a response `schema`.
+
+use serde_json::{Map, Value};
+
+/// The media types to take a schema from, in order of preference
+const MEDIA_TYPES: &[&str] = &["application/json", "text/json", "application/octet-stream", "*/*"];
+
+/// The OpenAPI 3.0 documents have an `openapi` version instead of a `swagger` version
+pub fn is_oas3(doc: &Value) -> bool {
+    doc.get("openapi").is_some() && doc.get("swagger").is_none()
+}
+
+/// Convert an OpenAPI 3.0 document to OpenAPI 2.0
+pub fn to_oas2(mut doc: Value) -> Value {
+    rewrite_refs(&mut doc);
+    let mut oas2 = Map::new();
+    oas2.insert("swagger".to_owned(), Value::String("2.0".to_owned()));
+    if let Value::Object(mut doc) = doc {
+        doc.remove("openapi");
+        doc.remove("servers");
+        let components = doc.remove("components");
+        let schemas = components
+            .as_ref()
+            .and_then(|components| components.get("schemas"))
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        for (key, value) in doc {
+            match key.as_str() {
+                "paths" => {
+                    oas2.insert(key, convert_paths(value, &schemas));
+                }
+                _ => {
+                    oas2.insert(key, value);
+                }
+            }
+        }
+        if let Some(Value::Object(mut components)) = components {
+            if let Some(schemas) = components.remove("schemas") {
+                oas2.insert("definitions".to_owned(), schemas);
+            }
+            if let Some(Value::Object(parameters)) = components.remove("parameters") {
+                let parameters = parameters
+                    .into_iter()
+                    .map(|(name, param)| (name, convert_parameter(param, &schemas)))
+                    .collect();
+                oas2.insert("parameters".to_owned(), Value::Object(parameters));
+            }
+        }
+    }
+    Value::Object(oas2)
+}
+
+/// Point the `$ref`s to the OpenAPI 2.0 locations
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        *reference = reference
+                            .replace("#/components/schemas/", "#/definitions/")
+                            .replace("#/components/parameters/", "#/parameters/");
+                    }
+                    value => rewrite_refs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+fn convert_paths(paths: Value, schemas: &Map<String, Value>) -> Value {
+    match paths {
+        Value::Object(paths) => Value::Object(
+            paths
+                .into_iter()
+                .map(|(path, item)| (path, convert_path_item(item, schemas)))
+                .collect(),
+        ),
+        paths => paths,
+    }
+}
+
+fn convert_path_item(item: Value, schemas: &Map<String, Value>) -> Value {
+    match item {
+        Value::Object(item) => Value::Object(
+            item.into_iter()
+                .map(|(key, value)| match key.as_str() {
+                    "parameters" => (key, convert_parameters(value, schemas)),
+                    "get" | "put" | "post" | "delete" | "options" | "head" | "patch" => (key, convert_operation(value, schemas)),
+                    _ => (key, value),
+                })
+                .collect(),
+        ),
+        item => item,
+    }
+}
+
+fn convert_parameters(parameters: Value, schemas: &Map<String, Value>) -> Value {
+    match parameters {
+        Value::Array(parameters) => Value::Array(parameters.into_iter().map(|param| convert_parameter(param, schemas)).collect()),
+        parameters => parameters,
+    }
+}
+
+/// A non-body parameter has its type inline instead of in a `schema`.
+/// A `schema` that is a reference to another file is kept, since there is nothing to inline.
+fn convert_parameter(param: Value, schemas: &Map<String, Value>) -> Value {
+    match param {
+        Value::Object(mut param) => {
+            if let Some(Value::Object(schema)) = param.remove("schema") {
+                match inline_schema(schema, schemas) {
+                    Ok(schema) => {
+                        for (key, value) in schema {
+                            param.entry(key).or_insert(value);
+                        }
+                    }
+                    Err(schema) => {
+                        param.insert("schema".to_owned(), Value::Object(schema));
+                    }
+                }
+            }
+            Value::Object(param)
+        }
+        param => param,
+    }
+}
+
+/// Replaces a `$ref` to one of the `components/schemas` with the referenced schema,
+/// or returns the schema as an error if the reference can not be inlined
+fn inline_schema(mut schema: Map<String, Value>, schemas: &Map<String, Value>) -> Result<Map<String, Value>, Map<String, Value>> {
+    let reference = match schema.get("$ref") {
+        Some(Value::String(reference)) => reference,
+        _ => return Ok(schema),
+    };
+    let referenced = reference
+        .strip_prefix("#/definitions/")
+        .and_then(|name| schemas.get(name))
+        .and_then(Value::as_object);
+    match referenced {
+        Some(referenced) => {
+            let mut referenced = referenced.clone();
+            schema.remove("$ref");
+            for (key, value) in schema {
+                referenced.insert(key, value);
+            }
+            Ok(referenced)
+        }
+        None => Err(schema),
+    }
+}
+
+fn convert_operation(operation: Value, schemas: &Map<String, Value>) -> Value {
+    let mut operation = match operation {
+        Value::Object(operation) => operation,
+        operation => return operation,
+    };
+    let mut parameters = match operation.remove("parameters") {
+        Some(Value::Array(parameters)) => parameters.into_iter().map(|param| convert_parameter(param, schemas)).collect(),
+        _ => Vec::new(),
+    };
+    if let Some(Value::Object(mut request_body)) = operation.remove("requestBody") {
+        let mut body = Map::new();
+        body.insert(
+            "name".to_owned(),
+            request_body
+                .remove("x-ms-requestBody-name")
+                .unwrap_or(Value::String("body".to_owned())),
+        );
+        body.insert("in".to_owned(), Value::String("body".to_owned()));
+        if let Some(required) = request_body.remove("required") {
+            body.insert("required".to_owned(), required);
+        }
+        if let Some(description) = request_body.remove("description") {
+            body.insert("description".to_owned(), description);
+        }
+        if let Some((media_type, schema)) = take_content_schema(&mut request_body) {
+            operation.insert("consumes".to_owned(), Value::Array(vec![Value::String(media_type)]));
+            body.insert("schema".to_owned(), schema);
+        }
+        parameters.push(Value::Object(body));
+    }
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_owned(), Value::Array(parameters));
+    }
+    if let Some(Value::Object(responses)) = operation.remove("responses") {
+        let responses = responses
+            .into_iter()
+            .map(|(status_code, rsp)| match rsp {
+                Value::Object(mut rsp) => {
+                    if let Some((_, schema)) = take_content_schema(&mut rsp) {
+                        rsp.insert("schema".to_owned(), schema);
+                    }
+                    if let Some(Value::Object(headers)) = rsp.remove("headers") {
+                        let headers = headers
+                            .into_iter()
+                            .map(|(name, header)| (name, convert_parameter(header, schemas)))
+                            .collect();
+                        rsp.insert("headers".to_owned(), Value::Object(headers));
+                    }
+                    (status_code, Value::Object(rsp))
+                }
+                rsp => (status_code, rsp),
+            })
+            .collect();
+        operation.insert("responses".to_owned(), Value::Object(responses));
+    }
+    Value::Object(operation)
+}
+
+/// Takes the schema of the preferred media type from the `content`
+fn take_content_schema(map: &mut Map<String, Value>) -> Option<(String, Value)> {
+    let mut content = match map.remove("content") {
+        Some(Value::Object(content)) => content,
+        _ => return None,
+    };
+    let media_type = MEDIA_TYPES
+        .iter()
+        .map(|media_type| media_type.to_string())
+        .find(|media_type| content.contains_key(media_type))
+        .or_else(|| content.keys().next().cloned())?;
+    match content.remove(&media_type) {
+        Some(Value::Object(mut media)) => media.remove("schema").map(|schema| (media_type, schema)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_oas3() {
+        assert!(is_oas3(&json!({ "openapi": "3.0.0" })));
+        assert!(!is_oas3(&json!({ "swagger": "2.0" })));
+    }
+
+    #[test]
+    fn test_to_oas2() {
+        let doc = json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Pets", "version": "1.0" },
+            "paths": {
+                "/pets/{name}": {
+                    "put": {
+                        "operationId": "Pets_Put",
+                        "parameters": [
+                            { "$ref": "#/components/parameters/ApiVersion" },
+                            { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                        ],
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                        },
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": { "Pet": { "type": "object" } },
+                "parameters": { "ApiVersion": { "name": "api-version", "in": "query", "schema": { "type": "string" } } }
+            }
+        });
+        let expected = json!({
+            "swagger": "2.0",
+            "info": { "title": "Pets", "version": "1.0" },
+            "paths": {
+                "/pets/{name}": {
+                    "put": {
+                        "operationId": "Pets_Put",
+                        "consumes": ["application/json"],
+                        "parameters": [
+                            { "$ref": "#/parameters/ApiVersion" },
+                            { "name": "name", "in": "path", "required": true, "type": "string" },
+                            { "name": "body", "in": "body", "required": true, "schema": { "$ref": "#/definitions/Pet" } }
+                        ],
+                        "responses": {
+                            "200": { "description": "OK", "schema": { "$ref": "#/definitions/Pet" } }
+                        }
+                    }
+                }
+            },
+            "definitions": { "Pet": { "type": "object" } },
+            "parameters": { "ApiVersion": { "name": "api-version", "in": "query", "type": "string" } }
+        });
+        assert_eq!(to_oas2(doc), expected);
+    }
+
+    #[test]
+    fn test_to_oas2_inlines_referenced_parameter_schemas() {
+        let doc = json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "parameters": [
+                            { "name": "kind", "in": "query", "schema": { "$ref": "#/components/schemas/Kind" } },
+                            { "name": "owner", "in": "query", "schema": { "$ref": "./owners.json#/components/schemas/Owner" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "headers": { "x-ms-kind": { "schema": { "$ref": "#/components/schemas/Kind" } } }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": { "Kind": { "type": "string", "enum": ["cat", "dog"] } }
+            }
+        });
+        let expected = json!({
+            "swagger": "2.0",
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "parameters": [
+                            { "name": "kind", "in": "query", "type": "string", "enum": ["cat", "dog"] },
+                            { "name": "owner", "in": "query", "schema": { "$ref": "./owners.json#/definitions/Owner" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "headers": { "x-ms-kind": { "type": "string", "enum": ["cat", "dog"] } }
+                            }
+                        }
+                    }
+                }
+            },
+            "definitions": { "Kind": { "type": "string", "enum": ["cat", "dog"] } }
+        });
+        assert_eq!(to_oas2(doc), expected);
+    }
+}
@@ -1,27 +1,46 @@
-use path_abs::PathMut;
-
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("PopUpPath")]
-    PopUpPath { source: path_abs::Error },
-    #[error("AppendPath")]
-    AppendPath { source: path_abs::Error },
+    #[error("{} goes above the root", path.display())]
+    AboveRoot { path: PathBuf },
 }
 
 /// Joins two files paths together
 ///
 /// If the first path ends with a file name (i.e., the last component has a file extension),
 /// the file component is dropped from that path.
+/// If the second path is absolute, it is returned as is.
+/// The `.` and `..` components are resolved without the file system, so the files do not need to exist.
 pub fn join<P1: AsRef<Path>, P2: AsRef<Path>>(a: P1, b: P2) -> Result<PathBuf> {
+    if b.as_ref().is_absolute() {
+        return Ok(b.as_ref().to_owned());
+    }
     let mut c = PathBuf::from(a.as_ref());
     if c.extension().is_some() {
-        c.pop_up().map_err(|source| Error::PopUpPath { source })?; // to directory
+        c.pop(); // to directory
+    }
+    normalize(&c.join(b))
+}
+
+/// Removes the `.` components and the components before each `..`, keeping the leading `..` of a relative path
+fn normalize(path: &Path) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => return Err(Error::AboveRoot { path: path.to_owned() }),
+                _ => normalized.push(Component::ParentDir),
+            },
+            component => normalized.push(component),
+        }
     }
-    c.append(&b).map_err(|source| Error::AppendPath { source })?;
-    Ok(c)
+    Ok(normalized)
 }
 
 #[cfg(test)]
@@ -39,4 +58,41 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_path_join_absolute() -> Result<()> {
+        let a = "../azure-rest-api-specs/specification/vmware/resource-manager/Microsoft.AVS/stable/2020-03-20/vmware.json";
+        #[cfg(not(windows))]
+        let b = "/specs/common-types/resource-management/v1/types.json";
+        #[cfg(windows)]
+        let b = r"C:\specs\common-types\resource-management\v1\types.json";
+        assert_eq!(join(a, b)?, PathBuf::from(b));
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_path_join_windows_path_on_unix() -> Result<()> {
+        // a Windows path is not absolute on other hosts, so it is a file name like any other
+        let b = r"C:\specs\types.json";
+        assert_eq!(join("specs/vmware.json", b)?, PathBuf::from("specs").join(b));
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_join_normalize() -> Result<()> {
+        assert_eq!(
+            join("specs/a/./b/file.json", "./../c/types.json")?,
+            PathBuf::from("specs/a/c/types.json")
+        );
+        assert_eq!(join("file.json", "../../types.json")?, PathBuf::from("../../types.json"));
+        assert_eq!(join("a/b", "../..")?, PathBuf::from(""));
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_path_join_above_root() {
+        assert!(join("/specs/file.json", "../../types.json").is_err());
+    }
 }
@@ -0,0 +1,124 @@
+//! Strongly connected components of a directed graph, using Tarjan's algorithm.
+//! Used to find recursive types.
+
+use indexmap::{IndexMap, IndexSet};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// A directed graph as a map from each node to the nodes it has edges to
+pub type Graph<N> = IndexMap<N, IndexSet<N>>;
+
+struct Tarjan<'a, N> {
+    graph: &'a Graph<N>,
+    index: usize,
+    indexes: HashMap<&'a N, usize>,
+    low_links: HashMap<&'a N, usize>,
+    stack: Vec<&'a N>,
+    on_stack: HashSet<&'a N>,
+    components: Vec<Vec<N>>,
+}
+
+impl<'a, N: Clone + Eq + Hash> Tarjan<'a, N> {
+    fn visit(&mut self, node: &'a N) {
+        self.indexes.insert(node, self.index);
+        self.low_links.insert(node, self.index);
+        self.index += 1;
+        self.stack.push(node);
+        self.on_stack.insert(node);
+
+        if let Some(targets) = self.graph.get(node) {
+            for target in targets {
+                // only nodes in the graph are visited
+                let target = match self.graph.get_key_value(target) {
+                    Some((target, _)) => target,
+                    None => continue,
+                };
+                if !self.indexes.contains_key(target) {
+                    self.visit(target);
+                    let low_link = self.low_links[target].min(self.low_links[node]);
+                    self.low_links.insert(node, low_link);
+                } else if self.on_stack.contains(target) {
+                    let low_link = self.indexes[target].min(self.low_links[node]);
+                    self.low_links.insert(node, low_link);
+                }
+            }
+        }
+
+        if self.low_links[node] == self.indexes[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member.clone());
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Gets the strongly connected components of the graph
+pub fn strongly_connected_components<N: Clone + Eq + Hash>(graph: &Graph<N>) -> Vec<Vec<N>> {
+    let mut tarjan = Tarjan {
+        graph,
+        index: 0,
+        indexes: HashMap::new(),
+        low_links: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for node in graph.keys() {
+        if !tarjan.indexes.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.components
+}
+
+/// Gets the components that have a cycle, either more than one node or a node with an edge to itself
+pub fn cycles<N: Clone + Eq + Hash>(graph: &Graph<N>) -> Vec<Vec<N>> {
+    strongly_connected_components(graph)
+        .into_iter()
+        .filter(|component| component.len() > 1 || graph.get(&component[0]).map_or(false, |targets| targets.contains(&component[0])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&'static str, &'static str)]) -> Graph<&'static str> {
+        let mut graph = Graph::new();
+        for (from, to) in edges {
+            graph.entry(*from).or_insert_with(IndexSet::new).insert(*to);
+            graph.entry(*to).or_insert_with(IndexSet::new);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_no_cycles() {
+        let graph = graph(&[("a", "b"), ("b", "c"), ("a", "c")]);
+        assert_eq!(strongly_connected_components(&graph).len(), 3);
+        assert!(cycles(&graph).is_empty());
+    }
+
+    #[test]
+    fn test_self_reference() {
+        let graph = graph(&[("a", "a"), ("a", "b")]);
+        assert_eq!(cycles(&graph), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn test_mutual_reference() {
+        let graph = graph(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d")]);
+        let mut cycles = cycles(&graph);
+        assert_eq!(cycles.len(), 1);
+        cycles[0].sort();
+        assert_eq!(cycles[0], vec!["a", "b", "c"]);
+    }
+}
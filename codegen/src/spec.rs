@@ -1,18 +1,24 @@
-use crate::path;
+use crate::{oas3, path};
 use autorust_openapi::{AdditionalProperties, OpenAPI, Operation, Parameter, PathItem, Reference, ReferenceOr, Schema};
-use heck::SnakeCase;
+use heck::{CamelCase, SnakeCase};
 use indexmap::{IndexMap, IndexSet};
+use percent_encoding::percent_decode_str;
+use rayon::prelude::*;
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsStr,
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 /// An API specification
 #[derive(Clone, Debug)]
 pub struct Spec {
     /// A store of all the documents for an API specification keyed on their file paths where the first one is the root document
-    docs: IndexMap<PathBuf, OpenAPI>,
+    ///
+    /// The paths of the same file share its document.
+    docs: IndexMap<PathBuf, Arc<OpenAPI>>,
     schemas: IndexMap<RefKey, Schema>,
     parameters: IndexMap<RefKey, Parameter>,
     input_files_paths: IndexSet<PathBuf>,
@@ -23,10 +29,12 @@ impl Spec {
     ///
     /// This eagerly collects all the schemas and parametes for the docs
     pub fn read_files<P: AsRef<Path>>(input_files_paths: &[P]) -> Result<Self> {
-        let mut docs: IndexMap<PathBuf, OpenAPI> = IndexMap::new();
+        let mut parsed = Spec::parse_files(input_files_paths)?;
+        let mut docs: IndexMap<PathBuf, Arc<OpenAPI>> = IndexMap::new();
         for file_path in input_files_paths {
-            Spec::read_file(&mut docs, file_path)?;
+            Spec::insert_doc(&mut docs, &mut parsed, file_path)?;
         }
+        Spec::disambiguate_schema_names(&mut docs)?;
 
         let mut schemas: IndexMap<RefKey, Schema> = IndexMap::new();
         let mut parameters: IndexMap<RefKey, Parameter> = IndexMap::new();
@@ -60,27 +68,302 @@ impl Spec {
         })
     }
 
-    /// Read a file and references too, recursively into the map
-    fn read_file<P: AsRef<Path>>(docs: &mut IndexMap<PathBuf, OpenAPI>, file_path: P) -> Result<()> {
+    /// Parse the input files and all the files they reference, a level of references at a time in parallel
+    ///
+    /// A file reached through a symlink or another relative path is only parsed once,
+    /// keyed on its canonical path.
+    fn parse_files<P: AsRef<Path>>(input_files_paths: &[P]) -> Result<HashMap<PathBuf, Arc<OpenAPI>>> {
+        let mut parsed: HashMap<PathBuf, Arc<OpenAPI>> = HashMap::new();
+        let mut seen_canonicals: HashMap<PathBuf, PathBuf> = HashMap::new();
+        let mut file_paths: Vec<PathBuf> = input_files_paths.iter().map(|f| f.as_ref().to_owned()).collect();
+        while !file_paths.is_empty() {
+            let mut to_parse: IndexSet<PathBuf> = IndexSet::new();
+            let mut aliases: Vec<(PathBuf, PathBuf)> = Vec::new();
+            for file_path in file_paths {
+                if parsed.contains_key(&file_path) || to_parse.contains(&file_path) {
+                    continue;
+                }
+                match fs::canonicalize(&file_path).ok() {
+                    Some(canonical) => match seen_canonicals.get(&canonical) {
+                        Some(seen_path) => aliases.push((file_path, seen_path.clone())),
+                        None => {
+                            seen_canonicals.insert(canonical, file_path.clone());
+                            to_parse.insert(file_path);
+                        }
+                    },
+                    None => {
+                        to_parse.insert(file_path);
+                    }
+                }
+            }
+
+            let to_parse: Vec<PathBuf> = to_parse.into_iter().collect();
+            let docs: Vec<(PathBuf, Result<OpenAPI>)> = to_parse
+                .into_par_iter()
+                .map(|file_path| {
+                    let doc = openapi::parse(&file_path);
+                    (file_path, doc)
+                })
+                .collect();
+            let mut new_docs: Vec<(PathBuf, Arc<OpenAPI>)> = Vec::new();
+            for (file_path, doc) in docs {
+                new_docs.push((file_path, Arc::new(doc?)));
+            }
+            // aliases reuse the parsed doc, but their references are relative to their own paths
+            for (file_path, seen_path) in aliases {
+                if parsed.contains_key(&file_path) {
+                    continue;
+                }
+                let doc = match parsed.get(&seen_path) {
+                    Some(doc) => Arc::clone(doc),
+                    None => match new_docs.iter().find(|(path, _)| path == &seen_path) {
+                        Some((_, doc)) => Arc::clone(doc),
+                        None => continue,
+                    },
+                };
+                new_docs.push((file_path, doc));
+            }
+
+            file_paths = Vec::new();
+            for (file_path, doc) in new_docs {
+                for ref_file in openapi::get_reference_file_paths(&doc) {
+                    file_paths.push(join_reference_file(&file_path, &ref_file)?);
+                }
+                parsed.insert(file_path, doc);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Insert a parsed doc and the docs it references, recursively into the map
+    ///
+    /// This is depth first in reference order, so that the order of the docs does not depend on the parsing order.
+    fn insert_doc<P: AsRef<Path>>(
+        docs: &mut IndexMap<PathBuf, Arc<OpenAPI>>,
+        parsed: &mut HashMap<PathBuf, Arc<OpenAPI>>,
+        file_path: P,
+    ) -> Result<()> {
         let file_path = file_path.as_ref();
         if !docs.contains_key(file_path) {
-            let doc = openapi::parse(&file_path)?;
-            let ref_files = openapi::get_reference_file_paths(&doc);
-            docs.insert(PathBuf::from(file_path), doc);
-            for ref_file in ref_files {
-                let child_path = path::join(&file_path, &ref_file).map_err(|source| Error::PathJoin { source })?;
-                Spec::read_file(docs, &child_path)?;
+            if let Some(doc) = parsed.remove(file_path) {
+                let ref_files = openapi::get_reference_file_paths(&doc);
+                docs.insert(PathBuf::from(file_path), doc);
+                for ref_file in ref_files {
+                    let child_path = join_reference_file(&file_path, &ref_file)?;
+                    Spec::insert_doc(docs, parsed, &child_path)?;
+                }
             }
         }
         Ok(())
     }
 
-    pub fn docs(&self) -> &IndexMap<PathBuf, OpenAPI> {
+    /// Rename the schemas that have the same name as a different schema in an earlier doc
+    ///
+    /// The later schema is prefixed with the stem of its file name, such as `BlobStorageBlob` for `StorageBlob` in `blob.json`,
+    /// and the references to it are updated, so that a type is generated for each.
+    fn disambiguate_schema_names(docs: &mut IndexMap<PathBuf, Arc<OpenAPI>>) -> Result<()> {
+        let mut first_schemas: HashMap<&str, &Schema> = HashMap::new();
+        let mut renames: HashMap<RefKey, String> = HashMap::new();
+        for (file_path, doc) in docs.iter() {
+            for (name, schema) in &doc.definitions {
+                if let ReferenceOr::Item(schema) = schema {
+                    match first_schemas.get(name.as_str()) {
+                        Some(first) if *first != schema => {
+                            let stem = file_path.file_stem().and_then(OsStr::to_str).unwrap_or_default();
+                            let ref_key = RefKey {
+                                file_path: file_path.clone(),
+                                name: name.clone(),
+                            };
+                            renames.insert(ref_key, format!("{}{}", stem.to_camel_case(), name));
+                        }
+                        Some(_) => {}
+                        None => {
+                            first_schemas.insert(name, schema);
+                        }
+                    }
+                }
+            }
+        }
+        if renames.is_empty() {
+            return Ok(());
+        }
+
+        for (file_path, doc) in docs.iter_mut() {
+            // a doc shared by another path is copied, since the references are relative to the path
+            let doc = Arc::make_mut(doc);
+            doc.definitions = std::mem::take(&mut doc.definitions)
+                .into_iter()
+                .map(|(name, schema)| {
+                    let ref_key = RefKey {
+                        file_path: file_path.clone(),
+                        name,
+                    };
+                    match renames.get(&ref_key) {
+                        Some(rename) => (rename.clone(), schema),
+                        None => (ref_key.name, schema),
+                    }
+                })
+                .collect();
+            let mut result = Ok(());
+            openapi::for_each_schema_reference_mut(doc, &mut |reference| {
+                let name = match &reference.name {
+                    Some(name) => name.clone(),
+                    None => return,
+                };
+                let full_path = match &reference.file {
+                    None => file_path.clone(),
+                    Some(file) => match join_reference_file(file_path, file) {
+                        Ok(full_path) => full_path,
+                        Err(error) => {
+                            result = Err(error);
+                            return;
+                        }
+                    },
+                };
+                let ref_key = RefKey {
+                    file_path: full_path,
+                    name,
+                };
+                if let Some(rename) = renames.get(&ref_key) {
+                    reference.name = Some(rename.clone());
+                }
+            });
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Combine two specs, such as ones loaded from different readme files
+    ///
+    /// A schema or parameter that is in both must be the same in each.
+    pub fn merge(mut self, other: Spec) -> Result<Spec> {
+        for (ref_key, schema) in other.schemas {
+            match self.schemas.get(&ref_key) {
+                Some(existing) if existing != &schema => return Err(Error::SchemaConflict { ref_key }),
+                Some(_) => {}
+                None => {
+                    self.schemas.insert(ref_key, schema);
+                }
+            }
+        }
+        for (ref_key, parameter) in other.parameters {
+            match self.parameters.get(&ref_key) {
+                Some(existing) if existing != &parameter => return Err(Error::ParameterConflict { ref_key }),
+                Some(_) => {}
+                None => {
+                    self.parameters.insert(ref_key, parameter);
+                }
+            }
+        }
+        for (file_path, doc) in other.docs {
+            self.docs.entry(file_path).or_insert(doc);
+        }
+        self.input_files_paths.extend(other.input_files_paths);
+        Ok(self)
+    }
+
+    /// Checks the input docs for common defects that may cause code generation to fail or generate poor code
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+
+        // schemas referenced from any doc
+        let mut referenced: HashSet<RefKey> = HashSet::new();
+        for (file_path, doc) in &self.docs {
+            for reference in openapi::get_references(doc) {
+                if let TypedReference::Schema(reference) = reference {
+                    if let (Some(name), Ok(ref_file_path)) = (reference.name, self.reference_file_path(file_path, reference.file)) {
+                        referenced.insert(RefKey {
+                            file_path: ref_file_path,
+                            name,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (file_path, doc) in self.input_docs() {
+            for (path, item) in &doc.paths {
+                if let ReferenceOr::Item(item) = item {
+                    for verb in path_item_operations(item) {
+                        if verb.operation().operation_id.is_none() {
+                            warnings.push(LintWarning {
+                                severity: LintSeverity::Warning,
+                                location: format!("{}#/paths/{}/{}", file_path.display(), path, verb.verb_name()),
+                                message: "operation has no operationId".to_owned(),
+                            });
+                        }
+                    }
+                }
+            }
+
+            for (name, schema) in &doc.definitions {
+                let location = format!("{}#/definitions/{}", file_path.display(), name);
+                let ref_key = RefKey {
+                    file_path: file_path.clone(),
+                    name: name.clone(),
+                };
+                if !referenced.contains(&ref_key) {
+                    warnings.push(LintWarning {
+                        severity: LintSeverity::Info,
+                        location: location.clone(),
+                        message: "definition is not referenced".to_owned(),
+                    });
+                }
+                if let ReferenceOr::Item(schema) = schema {
+                    if schema.common.type_.is_none() && schema.properties.is_empty() && schema.all_of.is_empty() {
+                        warnings.push(LintWarning {
+                            severity: LintSeverity::Warning,
+                            location: location.clone(),
+                            message: "schema has no type".to_owned(),
+                        });
+                    }
+                    lint_schema(&mut warnings, &location, schema);
+                }
+            }
+        }
+        warnings
+    }
+
+    fn reference_file_path(&self, doc_path: &Path, file: Option<String>) -> Result<PathBuf> {
+        match file {
+            None => Ok(doc_path.to_owned()),
+            Some(file) => join_reference_file(doc_path, &file),
+        }
+    }
+
+    /// Checks that all the references in the docs resolve, returning the ones that do not
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (file_path, doc) in &self.docs {
+            for reference in openapi::get_references(doc) {
+                let result = match &reference {
+                    TypedReference::PathItem(path_ref) => self.resolve_path_ref(file_path, path_ref.clone()).map(|_| ()),
+                    TypedReference::Parameter(param_ref) => self.resolve_parameter_ref(file_path, param_ref.clone()).map(|_| ()),
+                    TypedReference::Schema(schema_ref) => self.resolve_schema_ref(file_path, schema_ref.clone()).map(|_| ()),
+                    // examples are not loaded
+                    TypedReference::Example(_) => Ok(()),
+                };
+                if let Err(error) = result {
+                    errors.push(ValidationError {
+                        file_path: file_path.clone(),
+                        reference,
+                        error,
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    pub fn docs(&self) -> &IndexMap<PathBuf, Arc<OpenAPI>> {
         &self.docs
     }
 
     pub fn input_docs<'a>(&'a self) -> impl Iterator<Item = (&'a PathBuf, &'a OpenAPI)> {
-        self.docs.iter().filter(move |(p, _)| self.is_input_file(p))
+        self.docs
+            .iter()
+            .filter(move |(p, _)| self.is_input_file(p))
+            .map(|(p, doc)| (p, doc.as_ref()))
     }
 
     pub fn is_input_file<P: AsRef<Path>>(&self, path: P) -> bool {
@@ -92,7 +375,7 @@ impl Spec {
         let doc_path = doc_path.as_ref();
         let full_path = match reference.file {
             None => doc_path.to_owned(),
-            Some(file) => path::join(doc_path, &file).map_err(|source| Error::PathJoin { source })?,
+            Some(file) => join_reference_file(doc_path, &file)?,
         };
 
         let name = reference.name.ok_or_else(|| Error::NoNameInReference)?;
@@ -116,7 +399,7 @@ impl Spec {
         let doc_path = doc_path.as_ref();
         let full_path = match reference.file {
             None => doc_path.to_owned(),
-            Some(file) => path::join(doc_path, &file).map_err(|source| Error::PathJoin { source })?,
+            Some(file) => join_reference_file(doc_path, &file)?,
         };
         let name = reference.name.ok_or_else(|| Error::NoNameInReference)?;
         let ref_key = RefKey {
@@ -163,14 +446,33 @@ impl Spec {
         Ok(resolved)
     }
 
-    pub fn resolve_path<P: AsRef<Path>>(&self, _doc_path: P, path: &ReferenceOr<PathItem>) -> Result<PathItem> {
+    /// Find the path item for a given doc path and reference
+    pub fn resolve_path_ref<P: AsRef<Path>>(&self, doc_path: P, reference: Reference) -> Result<PathItem> {
+        let doc_path = doc_path.as_ref();
+        let full_path = match reference.file {
+            None => doc_path.to_owned(),
+            Some(file) => join_reference_file(doc_path, &file)?,
+        };
+
+        let name = reference.name.ok_or_else(|| Error::NoNameInReference)?;
+        // the name is a JSON pointer segment, so a path like `/subscriptions/{id}` is escaped as `~1subscriptions~1{id}`
+        let path = name.replace("~1", "/").replace("~0", "~");
+        let path_item = match self.docs.get(&full_path).and_then(|doc| doc.paths.get(&path)) {
+            Some(path_item) => path_item,
+            None => {
+                return Err(Error::PathItemNotFound {
+                    file_path: full_path,
+                    path,
+                })
+            }
+        };
+        self.resolve_path(&full_path, path_item)
+    }
+
+    pub fn resolve_path<P: AsRef<Path>>(&self, doc_path: P, path: &ReferenceOr<PathItem>) -> Result<PathItem> {
         match path {
             ReferenceOr::Item(path) => Ok(path.clone()),
-            ReferenceOr::Reference { .. } => {
-                // self.resolve_path_ref(doc_file, reference),
-                // TODO
-                Err(Error::NotImplemented)
-            }
+            ReferenceOr::Reference { reference, .. } => self.resolve_path_ref(doc_path, reference.clone()),
         }
     }
 
@@ -210,8 +512,12 @@ pub enum Error {
     NoNameInReference,
     #[error("ParameterNotFound")]
     ParameterNotFound { ref_key: RefKey },
-    #[error("NotImplemented")]
-    NotImplemented,
+    #[error("SchemaConflict {} {}", ref_key.file_path.display(), ref_key.name)]
+    SchemaConflict { ref_key: RefKey },
+    #[error("ParameterConflict {} {}", ref_key.file_path.display(), ref_key.name)]
+    ParameterConflict { ref_key: RefKey },
+    #[error("PathItemNotFound {} {}", file_path.display(), path)]
+    PathItemNotFound { file_path: PathBuf, path: String },
     #[error("ReadFile")]
     ReadFile { source: std::io::Error, path: PathBuf },
     #[error("DeserializeYaml")]
@@ -220,6 +526,73 @@ pub enum Error {
     DeserializeJson { source: serde_json::Error, path: PathBuf },
 }
 
+/// A reference in a doc that does not resolve
+#[derive(Debug, thiserror::Error)]
+#[error("{} has an unresolved {}: {}", file_path.display(), reference, error)]
+pub struct ValidationError {
+    pub file_path: PathBuf,
+    pub reference: TypedReference,
+    pub error: Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Info,
+    Warning,
+}
+
+/// A possible defect in a doc
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintWarning {
+    pub severity: LintSeverity,
+    pub location: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?} {}: {}", self.severity, self.location, self.message)
+    }
+}
+
+/// Checks the enums, property names, and allOf of a schema
+fn lint_schema(warnings: &mut Vec<LintWarning>, location: &str, schema: &Schema) {
+    let mut enum_values = HashSet::new();
+    for value in &schema.common.enum_ {
+        if !enum_values.insert(value.to_string()) {
+            warnings.push(LintWarning {
+                severity: LintSeverity::Warning,
+                location: location.to_owned(),
+                message: format!("enum has duplicate value {}", value),
+            });
+        }
+    }
+
+    let mut field_names: HashMap<String, &str> = HashMap::new();
+    for (property_name, property) in &schema.properties {
+        if let Some(other) = field_names.insert(property_name.to_snake_case(), property_name) {
+            warnings.push(LintWarning {
+                severity: LintSeverity::Warning,
+                location: location.to_owned(),
+                message: format!("properties {} and {} have the same snake case name", other, property_name),
+            });
+        }
+        if let ReferenceOr::Item(property) = property {
+            lint_schema(warnings, &format!("{}/properties/{}", location, property_name), property);
+        }
+    }
+
+    for all_of in &schema.all_of {
+        if let ReferenceOr::Item(_) = all_of {
+            warnings.push(LintWarning {
+                severity: LintSeverity::Warning,
+                location: location.to_owned(),
+                message: "allOf has an inline schema instead of a $ref".to_owned(),
+            });
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct RefKey {
     pub file_path: PathBuf,
@@ -242,7 +615,27 @@ pub mod openapi {
             source,
             path: PathBuf::from(path),
         })?;
-        let api = if path.extension() == Some(OsStr::new("yaml")) || path.extension() == Some(OsStr::new("yml")) {
+        let is_yaml = path.extension() == Some(OsStr::new("yaml")) || path.extension() == Some(OsStr::new("yml"));
+        if may_be_oas3(&bytes) {
+            let doc: serde_json::Value = if is_yaml {
+                serde_yaml::from_slice(&bytes).map_err(|source| Error::DeserializeYaml {
+                    source,
+                    path: PathBuf::from(path),
+                })?
+            } else {
+                serde_json::from_slice(&bytes).map_err(|source| Error::DeserializeJson {
+                    source,
+                    path: PathBuf::from(path),
+                })?
+            };
+            if oas3::is_oas3(&doc) {
+                return serde_json::from_value(oas3::to_oas2(doc)).map_err(|source| Error::DeserializeJson {
+                    source,
+                    path: PathBuf::from(path),
+                });
+            }
+        }
+        let api = if is_yaml {
             serde_yaml::from_slice(&bytes).map_err(|source| Error::DeserializeYaml {
                 source,
                 path: PathBuf::from(path),
@@ -257,6 +650,12 @@ pub mod openapi {
         Ok(api)
     }
 
+    /// A quick check for an `openapi` key, before parsing the doc to see if it is OpenAPI 3.0
+    fn may_be_oas3(bytes: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(bytes);
+        text.contains("\"openapi\"") || text.lines().any(|line| line.starts_with("openapi:"))
+    }
+
     /// Returns a set of referenced relative file paths from an OpenAPI specficiation
     pub fn get_reference_file_paths(api: &OpenAPI) -> IndexSet<String> {
         get_references(api)
@@ -325,6 +724,53 @@ pub mod openapi {
         list
     }
 
+    /// Calls the function with each schema reference in an Open API specification, so that it may be changed
+    pub fn for_each_schema_reference_mut(api: &mut OpenAPI, f: &mut dyn FnMut(&mut Reference)) {
+        for (_path, item) in api.paths.iter_mut() {
+            if let ReferenceOr::Item(item) = item {
+                let operations = vec![
+                    item.get.as_mut(),
+                    item.post.as_mut(),
+                    item.put.as_mut(),
+                    item.patch.as_mut(),
+                    item.delete.as_mut(),
+                    item.options.as_mut(),
+                    item.head.as_mut(),
+                ];
+                for op in operations.into_iter().flatten() {
+                    for param in op.parameters.iter_mut() {
+                        if let ReferenceOr::Item(parameter) = param {
+                            for_each_parameter_reference_mut(parameter, f);
+                        }
+                    }
+                    for (_code, rsp) in op.responses.iter_mut() {
+                        if let Some(schema) = rsp.schema.as_mut() {
+                            for_each_reference_or_schema_mut(schema, f);
+                        }
+                        // a header has its type inline, like a non-body parameter
+                        for (_name, header) in rsp.headers.iter_mut() {
+                            if let Some(schema) = header.items.as_mut() {
+                                for_each_reference_or_schema_mut(schema, f);
+                            }
+                        }
+                    }
+                }
+                // the parameters of the path item are shared by its operations
+                for param in item.parameters.iter_mut() {
+                    if let ReferenceOr::Item(parameter) = param {
+                        for_each_parameter_reference_mut(parameter, f);
+                    }
+                }
+            }
+        }
+        for (_name, parameter) in api.parameters.iter_mut() {
+            for_each_parameter_reference_mut(parameter, f);
+        }
+        for (_name, schema) in api.definitions.iter_mut() {
+            for_each_reference_or_schema_mut(schema, f);
+        }
+    }
+
     /// Get all references related to schemas for an Open API specification
     pub fn get_api_schema_references(api: &OpenAPI) -> Vec<Reference> {
         get_references(api)
@@ -380,6 +826,23 @@ impl<'a> OperationVerb<'a> {
             (None, create_function_name(path, self.verb_name()))
         }
     }
+
+    /// Returns the module name and function name, where the module is the first tag if there is one.
+    /// The operationId prefix is kept in the function name if it is not the tag, so that the names in a module are unique.
+    pub fn module_and_function_name(&self, path: &str) -> (Option<String>, String) {
+        let (id_module_name, function_name) = self.function_name(path);
+        match self.operation().tags.first() {
+            Some(tag) => {
+                let module_name = tag.to_snake_case();
+                let function_name = match id_module_name {
+                    Some(id_module_name) if id_module_name != module_name => format!("{}_{}", id_module_name, function_name),
+                    _ => function_name,
+                };
+                (Some(module_name), function_name)
+            }
+            None => (id_module_name, function_name),
+        }
+    }
 }
 
 /// Returns the module name and function name.
@@ -424,6 +887,28 @@ pub enum TypedReference {
     Example(Reference),
 }
 
+impl TypedReference {
+    /// The underlying reference, whatever its kind
+    pub fn as_reference(&self) -> &Reference {
+        match self {
+            TypedReference::PathItem(r) => r,
+            TypedReference::Parameter(r) => r,
+            TypedReference::Schema(r) => r,
+            TypedReference::Example(r) => r,
+        }
+    }
+
+    /// The kind of reference: `path`, `parameter`, `schema`, or `example`
+    pub fn kind_str(&self) -> &'static str {
+        match self {
+            TypedReference::PathItem(_) => "path",
+            TypedReference::Parameter(_) => "parameter",
+            TypedReference::Schema(_) => "schema",
+            TypedReference::Example(_) => "example",
+        }
+    }
+}
+
 impl Into<Reference> for TypedReference {
     fn into(self) -> Reference {
         match self {
@@ -435,6 +920,23 @@ impl Into<Reference> for TypedReference {
     }
 }
 
+impl std::fmt::Display for TypedReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, section) = match self {
+            TypedReference::PathItem(_) => ("Path", "paths"),
+            TypedReference::Parameter(_) => ("Parameter", "parameters"),
+            TypedReference::Schema(_) => ("Schema", "definitions"),
+            TypedReference::Example(_) => ("Example", ""),
+        };
+        let reference = self.as_reference();
+        write!(f, "{} ref: {}", kind, reference.file.as_deref().unwrap_or_default())?;
+        if let Some(name) = &reference.name {
+            write!(f, "#/{}/{}", section, name)?;
+        }
+        Ok(())
+    }
+}
+
 /// Get all schema references for a given schema
 pub fn get_schema_schema_references(schema: &Schema) -> Vec<Reference> {
     let mut refs = Vec::new();
@@ -479,10 +981,65 @@ fn add_references_for_schema(list: &mut Vec<TypedReference>, schema: &Schema) {
     }
 }
 
+fn for_each_reference_or_schema_mut(schema: &mut ReferenceOr<Schema>, f: &mut dyn FnMut(&mut Reference)) {
+    match schema {
+        ReferenceOr::Reference { reference, .. } => f(reference),
+        ReferenceOr::Item(schema) => for_each_schema_reference_mut(schema, f),
+    }
+}
+
+fn for_each_parameter_reference_mut(parameter: &mut Parameter, f: &mut dyn FnMut(&mut Reference)) {
+    if let Some(schema) = parameter.schema.as_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+    if let Some(schema) = parameter.common.items.as_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+}
+
+fn for_each_schema_reference_mut(schema: &mut Schema, f: &mut dyn FnMut(&mut Reference)) {
+    for (_, schema) in schema.properties.iter_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+    if let Some(AdditionalProperties::Schema(schema)) = schema.additional_properties.as_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+    if let Some(schema) = schema.common.items.as_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+    for schema in schema.all_of.iter_mut() {
+        for_each_reference_or_schema_mut(schema, f);
+    }
+}
+
+/// Joins the file of a reference to the path of the doc it is in, decoding the percent-encoded characters of the file
+pub(crate) fn join_reference_file<P: AsRef<Path>>(doc_path: P, file: &str) -> Result<PathBuf> {
+    let file = percent_decode_str(file).decode_utf8_lossy();
+    path::join(doc_path, &*file).map_err(|source| Error::PathJoin { source })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_join_reference_file() -> Result<()> {
+        let doc = "specification/storage/stable/2021-01-01/storage.json";
+        assert_eq!(
+            join_reference_file(doc, "../common%20types/types.json")?,
+            PathBuf::from("specification/storage/stable/common types/types.json")
+        );
+        assert_eq!(
+            join_reference_file(doc, "./examples/Get%28Blob%29%2Bv2.json")?,
+            PathBuf::from("specification/storage/stable/2021-01-01/examples/Get(Blob)+v2.json")
+        );
+        assert_eq!(
+            join_reference_file(doc, "types.json")?,
+            PathBuf::from("specification/storage/stable/2021-01-01/types.json")
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_create_function_name() {
         assert_eq!(create_function_name("/pets", "get"), "pets_get");
@@ -496,4 +1053,88 @@ mod tests {
         );
         assert_eq!(function_name_from_operation_id("get"), (None, "get".to_owned()));
     }
+
+    #[test]
+    fn test_module_and_function_name() -> Result<(), serde_json::Error> {
+        let operation: Operation = serde_json::from_value(serde_json::json!({
+            "operationId": "VirtualMachineImages_List",
+            "tags": ["Virtual Machines"],
+            "responses": {}
+        }))?;
+        let verb = OperationVerb::Get(&operation);
+        assert_eq!(
+            verb.module_and_function_name("/images"),
+            (Some("virtual_machines".to_owned()), "virtual_machine_images_list".to_owned())
+        );
+        let operation: Operation = serde_json::from_value(serde_json::json!({
+            "operationId": "VirtualMachines_List",
+            "tags": ["VirtualMachines"],
+            "responses": {}
+        }))?;
+        let verb = OperationVerb::Get(&operation);
+        assert_eq!(
+            verb.module_and_function_name("/vms"),
+            (Some("virtual_machines".to_owned()), "list".to_owned())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_typed_reference() -> Result<(), serde_json::Error> {
+        let doc = serde_json::from_value(serde_json::json!({
+            "swagger": "2.0",
+            "info": { "title": "Pets", "version": "1.0" },
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "operationId": "Pets_List",
+                        "parameters": [{ "$ref": "#/parameters/ApiVersion" }],
+                        "responses": { "200": { "description": "OK", "schema": { "$ref": "../types.json#/definitions/Foo" } } }
+                    }
+                }
+            }
+        }))?;
+        let references = openapi::get_references(&doc);
+        assert!(references
+            .iter()
+            .any(|reference| reference.kind_str() == "schema" && reference.as_reference().name.as_deref() == Some("Foo")));
+        let references: Vec<String> = references.iter().map(ToString::to_string).collect();
+        assert!(references.contains(&"Parameter ref: #/parameters/ApiVersion".to_owned()));
+        assert!(references.contains(&"Schema ref: ../types.json#/definitions/Foo".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_disambiguate_schema_names() -> Result<(), serde_json::Error> {
+        let storage = serde_json::from_value(serde_json::json!({
+            "swagger": "2.0",
+            "info": { "title": "Storage", "version": "1.0" },
+            "paths": {},
+            "definitions": {
+                "StorageBlob": { "type": "object", "properties": { "size": { "type": "integer" } } }
+            }
+        }))?;
+        let blob = serde_json::from_value(serde_json::json!({
+            "swagger": "2.0",
+            "info": { "title": "Blob", "version": "1.0" },
+            "paths": {},
+            "definitions": {
+                "StorageBlob": { "type": "object", "properties": { "name": { "type": "string" } } },
+                "Container": { "type": "object", "properties": { "blob": { "$ref": "#/definitions/StorageBlob" } } }
+            }
+        }))?;
+        let mut docs = IndexMap::new();
+        docs.insert(PathBuf::from("storage.json"), Arc::new(storage));
+        docs.insert(PathBuf::from("blob.json"), Arc::new(blob));
+        Spec::disambiguate_schema_names(&mut docs).unwrap();
+
+        assert!(docs[Path::new("storage.json")].definitions.contains_key("StorageBlob"));
+        let blob = &docs[Path::new("blob.json")];
+        assert!(!blob.definitions.contains_key("StorageBlob"));
+        assert!(blob.definitions.contains_key("BlobStorageBlob"));
+        let references = openapi::get_api_schema_references(blob);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].name.as_deref(), Some("BlobStorageBlob"));
+        Ok(())
+    }
 }